@@ -0,0 +1,561 @@
+use core::cmp;
+use core::convert::TryFrom;
+use core::fmt;
+
+use vector::Vector;
+use stack::Stack;
+
+use instrs::{Instr, ValueType};
+use decoder::{self, DecodeErrorKind};
+use encoding;
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StackErrorKind {
+    // instruction does not decode, the path is not followed further
+    Undecodable(DecodeErrorKind),
+    // pops more values than the stack holds
+    StackUnderflow,
+    // pops the first type where a value of the second type, of another width, is on top
+    WidthMismatch(ValueType, ValueType),
+    // paths joining at the instruction, or returns from the same function,
+    // leave stacks of different shapes
+    ShapeMismatch,
+    // jmp, if_jmp or call target is read from memory or popped from the stack,
+    // the path is not followed further
+    UnknownTarget,
+    // ret outside of any call
+    CallStackUnderflow,
+    // enter, call_args or spawn frame size is read from memory, the path is not followed further
+    UnknownFrame,
+    // pick, roll or drop_bytes operand is read from memory, the path is not followed further
+    UnknownDepth,
+}
+
+impl fmt::Display for StackErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StackErrorKind::Undecodable(kind) => write!(f, "{}", kind),
+            StackErrorKind::StackUnderflow => write!(f, "stack underflow"),
+            StackErrorKind::WidthMismatch(expected, found) => write!(f, "expected {} found {}", expected, found),
+            StackErrorKind::ShapeMismatch => write!(f, "inconsistent stack shape"),
+            StackErrorKind::UnknownTarget => write!(f, "unknown jump target"),
+            StackErrorKind::CallStackUnderflow => write!(f, "return outside of a call"),
+            StackErrorKind::UnknownFrame => write!(f, "unknown frame size"),
+            StackErrorKind::UnknownDepth => write!(f, "unknown stack depth"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct StackError {
+    kind: StackErrorKind,
+    offset: usize,
+}
+
+impl StackError {
+    #[inline]
+    pub fn new(kind: StackErrorKind, offset: usize) -> Self {
+        StackError {
+            kind: kind,
+            offset: offset,
+        }
+    }
+
+    #[inline]
+    pub fn get_kind(&self) -> StackErrorKind {self.kind}
+    #[inline]
+    pub fn get_offset(&self) -> usize {self.offset}
+}
+
+impl fmt::Display for StackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.offset)
+    }
+}
+
+
+#[inline]
+fn bytes(shape: &[ValueType]) -> usize {
+    shape.iter().map(|value_type| value_type.bytes()).sum()
+}
+
+// pick and roll, which reach as many values below the top as their operand
+#[inline]
+fn reaches(instr: Instr) -> bool {
+    matches!(instr,
+        Instr::pick_u8 | Instr::pick_u16 | Instr::pick_u32 | Instr::pick_u64 |
+        Instr::pick_i8 | Instr::pick_i16 | Instr::pick_i32 | Instr::pick_i64 | Instr::pick_f32 | Instr::pick_f64 |
+        Instr::roll_u8 | Instr::roll_u16 | Instr::roll_u32 | Instr::roll_u64 |
+        Instr::roll_i8 | Instr::roll_i16 | Instr::roll_i32 | Instr::roll_i64 | Instr::roll_f32 | Instr::roll_f64)
+}
+
+#[inline]
+fn same_widths(a: &[ValueType], b: &[ValueType]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.bits() == b.bits())
+}
+
+
+struct State {
+    // entry of the function the instruction runs in
+    entry: usize,
+    // values the function popped from below its entry stack
+    params: usize,
+    // values above the params, bottom first
+    shape: Vector<ValueType>,
+    // bytes reserved by enter below the shape
+    frame: usize,
+}
+
+impl State {
+    #[inline]
+    fn copy(&self) -> Self {
+        let mut shape = Vector::with_capacity(self.shape.len());
+        for value_type in self.shape.iter() {
+            shape.push(*value_type);
+        }
+        State {
+            entry: self.entry,
+            params: self.params,
+            shape: shape,
+            frame: self.frame,
+        }
+    }
+}
+
+struct Function {
+    entry: usize,
+    // types popped from below the entry stack, top first
+    params: Vector<ValueType>,
+    // params consumed and values left by the first ret reached
+    results: Option<(usize, Vector<ValueType>)>,
+    // offsets of the calls to the function, of the instructions they return
+    // to and the argument bytes they move into its frame
+    returns: Vector<(usize, usize, usize)>,
+    // deepest stack relative to the entry stack, in bytes
+    max_depth: isize,
+    // relative stack depth at each call and the called entry
+    calls: Vector<(isize, usize)>,
+}
+
+
+pub struct Analysis {
+    states: Vector<Option<State>>,
+    errors: Vector<StackError>,
+    max_depth: Option<usize>,
+}
+
+impl Analysis {
+    // problems found, ordered by offset
+    #[inline]
+    pub fn get_errors(&self) -> &[StackError] {&self.errors}
+    #[inline]
+    pub fn is_ok(&self) -> bool {self.errors.is_empty()}
+
+    // deepest the stack gets in bytes, None if the program recurses
+    #[inline]
+    pub fn get_max_depth(&self) -> Option<usize> {self.max_depth}
+
+    // types on the stack before the instruction at offset runs, bottom first,
+    // inside a function only the values above its arguments, None if unreached
+    #[inline]
+    pub fn get_shape(&self, offset: usize) -> Option<&[ValueType]> {
+        match self.states.get(offset) {
+            Some(Some(state)) => Some(&state.shape),
+            _ => None,
+        }
+    }
+}
+
+
+struct Analyzer<'a> {
+    program: &'a [u8],
+    states: Vector<Option<State>>,
+    functions: Vector<Function>,
+    errors: Vector<StackError>,
+    pending: Vector<usize>,
+}
+
+impl<'a> Analyzer<'a> {
+    fn report(&mut self, kind: StackErrorKind, offset: usize) {
+        let error = StackError::new(kind, offset);
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    #[inline]
+    fn depth(&self, function: usize, state: &State) -> isize {
+        let params = &self.functions[function].params[..state.params];
+        let used = isize::try_from(state.frame.saturating_add(bytes(&state.shape))).unwrap_or(isize::MAX);
+        used - bytes(params) as isize
+    }
+
+    fn function(&mut self, entry: usize) -> usize {
+        if let Some(index) = self.functions.iter().position(|function| function.entry == entry) {
+            return index;
+        }
+        self.functions.push(Function {
+            entry: entry,
+            params: Vector::new(),
+            results: None,
+            returns: Vector::new(),
+            max_depth: 0,
+            calls: Vector::new(),
+        });
+        self.propagate(entry, State {
+            entry: entry,
+            params: 0,
+            shape: Vector::new(),
+            frame: 0,
+        });
+        self.functions.len() - 1
+    }
+
+    fn propagate(&mut self, offset: usize, state: State) {
+        let function = self.function(state.entry);
+        let depth = self.depth(function, &state);
+        self.functions[function].max_depth = cmp::max(self.functions[function].max_depth, depth);
+
+        // running off the end of the program ends the path
+        if offset >= self.program.len() {
+            return;
+        }
+        let consistent = match self.states[offset] {
+            Some(ref known) => {
+                known.params == state.params && known.frame == state.frame && same_widths(&known.shape, &state.shape)
+            },
+            None => {
+                self.states[offset] = Some(state);
+                self.pending.push(offset);
+                true
+            },
+        };
+        if !consistent {
+            self.report(StackErrorKind::ShapeMismatch, offset);
+        }
+    }
+
+    // false if the path cannot continue
+    fn pop(&mut self, state: &mut State, function: usize, expected: ValueType, offset: usize) -> bool {
+        if let Some(found) = state.shape.pop() {
+            if found.bits() != expected.bits() {
+                self.report(StackErrorKind::WidthMismatch(expected, found), offset);
+            }
+            return true;
+        }
+        // the program starts on an empty stack
+        if function == 0 {
+            self.report(StackErrorKind::StackUnderflow, offset);
+            return false;
+        }
+
+        let found = self.functions[function].params.get(state.params).copied();
+        match found {
+            Some(found) if found.bits() != expected.bits() => {
+                self.report(StackErrorKind::WidthMismatch(expected, found), offset);
+            },
+            Some(_) => (),
+            None => self.functions[function].params.push(expected),
+        }
+        state.params += 1;
+        true
+    }
+
+    // bytes a pop can reach, the program's own stack holds only what it
+    // pushed, a function is assumed to take no more argument bytes than the
+    // program is long, this keeps operand sized loops bounded
+    #[inline]
+    fn available(&self, state: &State, function: usize) -> usize {
+        let params = if function == 0 {0} else {self.program.len()};
+        bytes(&state.shape).saturating_add(params)
+    }
+
+    // pops values making up bytes, false if the path cannot continue
+    fn pop_bytes(&mut self, state: &mut State, function: usize, bytes: usize, offset: usize) -> bool {
+        if bytes > self.available(state, function) {
+            self.report(StackErrorKind::StackUnderflow, offset);
+            return false;
+        }
+        let mut popped = 0;
+
+        while popped < bytes {
+            let value_type = match state.shape.last() {
+                Some(value_type) => *value_type,
+                None => ValueType::U8,
+            };
+            if !self.pop(state, function, value_type, offset) {
+                return false;
+            }
+            popped += value_type.bytes();
+        }
+        // a value straddles the arguments
+        if popped != bytes {
+            self.report(StackErrorKind::ShapeMismatch, offset);
+        }
+        true
+    }
+
+    fn call(&mut self, function: usize, state: State, offset: usize, target: usize, next: usize, args: usize) {
+        let depth = self.depth(function, &state);
+        let callee = self.function(target);
+
+        self.functions[function].calls.push((depth, target));
+        self.functions[callee].returns.push((offset, next, args));
+        if self.functions[callee].results.is_some() {
+            self.returned(callee, offset, next, args);
+        }
+    }
+
+    // continues the caller after callee returned to next
+    fn returned(&mut self, callee: usize, offset: usize, next: usize, args: usize) {
+        let mut state = match self.states[offset] {
+            Some(ref state) => state.copy(),
+            None => return,
+        };
+        let caller = self.function(state.entry);
+
+        if !self.pop_bytes(&mut state, caller, args, offset) {
+            return;
+        }
+
+        let params = match self.functions[callee].results {
+            Some((params, _)) => params,
+            None => return,
+        };
+        for index in 0..params {
+            let expected = self.functions[callee].params[index];
+            if !self.pop(&mut state, caller, expected, offset) {
+                return;
+            }
+        }
+        if let Some((_, ref results)) = self.functions[callee].results {
+            for value_type in results.iter() {
+                state.shape.push(*value_type);
+            }
+        }
+        self.propagate(next, state);
+    }
+
+    fn ret(&mut self, function: usize, state: State, offset: usize) {
+        if function == 0 {
+            self.report(StackErrorKind::CallStackUnderflow, offset);
+            return;
+        }
+
+        let consistent = match self.functions[function].results {
+            Some((params, ref results)) => params == state.params && same_widths(results, &state.shape),
+            None => {
+                self.functions[function].results = Some((state.params, state.shape));
+                for index in 0..self.functions[function].returns.len() {
+                    let (call, next, args) = self.functions[function].returns[index];
+                    self.returned(function, call, next, args);
+                }
+                true
+            },
+        };
+        if !consistent {
+            self.report(StackErrorKind::ShapeMismatch, offset);
+        }
+    }
+
+    fn transfer(&mut self, offset: usize) {
+        let decoded = match decoder::decode(self.program, offset) {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                self.report(StackErrorKind::Undecodable(error.get_kind()), error.get_offset());
+                return;
+            },
+        };
+        let mut state = match self.states[offset] {
+            Some(ref state) => state.copy(),
+            None => return,
+        };
+        let function = self.function(state.entry);
+        let next = decoded.get_end();
+
+        match decoded.get_instr() {
+            Instr::halt => (),
+            Instr::halt_with => {
+                self.pop(&mut state, function, ValueType::U32, offset);
+            },
+            Instr::ret => self.ret(function, state, offset),
+
+            Instr::jmp | Instr::jmp_rel => match decoded.get_target() {
+                Some(target) => self.propagate(target, state),
+                None => self.report(StackErrorKind::UnknownTarget, offset),
+            },
+            // if_jmp, if_jmp_rel and the fused br_* branches
+            instr if decoder::is_branch(instr) && !instr.pops().is_empty() => {
+                for value_type in instr.pops().iter() {
+                    if !self.pop(&mut state, function, *value_type, offset) {
+                        return;
+                    }
+                }
+                match decoded.get_target() {
+                    Some(target) => {
+                        let taken = state.copy();
+                        self.propagate(target, taken);
+                    },
+                    None => self.report(StackErrorKind::UnknownTarget, offset),
+                }
+                self.propagate(next, state);
+            },
+            Instr::call | Instr::call_rel => match decoded.get_target() {
+                Some(target) if target < self.program.len() => self.call(function, state, offset, target, next, 0),
+                // outside of the program, left to the verifier
+                Some(_) => (),
+                None => self.report(StackErrorKind::UnknownTarget, offset),
+            },
+            Instr::call_args => {
+                let args = decoded.get_operands()[1];
+                if args.get_mode() != Instr::type_int {
+                    return self.report(StackErrorKind::UnknownFrame, offset);
+                }
+
+                match decoded.get_target() {
+                    Some(target) if target < self.program.len() => {
+                        self.call(function, state, offset, target, next, args.get_value() as usize);
+                    },
+                    Some(_) => (),
+                    None => self.report(StackErrorKind::UnknownTarget, offset),
+                }
+            },
+            // the child starts like a function called with the arguments, on
+            // its own stack
+            Instr::spawn => {
+                let args = decoded.get_operands()[1];
+                if args.get_mode() != Instr::type_int {
+                    return self.report(StackErrorKind::UnknownFrame, offset);
+                }
+                if !self.pop_bytes(&mut state, function, args.get_value() as usize, offset) {
+                    return;
+                }
+
+                match decoded.get_target() {
+                    Some(target) if target < self.program.len() => {
+                        self.function(target);
+                    },
+                    Some(_) => (),
+                    None => self.report(StackErrorKind::UnknownTarget, offset),
+                }
+                state.shape.push(ValueType::U64);
+                self.propagate(next, state);
+            },
+            Instr::enter => {
+                let locals = match decoded.get_operand() {
+                    Some(locals) if locals.get_mode() == Instr::type_int => locals.get_value() as usize,
+                    _ => return self.report(StackErrorKind::UnknownFrame, offset),
+                };
+                // values pushed so far become part of the frame
+                let frame = state.frame.checked_add(bytes(&state.shape)).and_then(|frame| frame.checked_add(locals));
+                state.frame = match frame {
+                    Some(frame) if frame <= isize::MAX as usize => frame,
+                    _ => return self.report(StackErrorKind::UnknownFrame, offset),
+                };
+                state.shape = Vector::new();
+                self.propagate(next, state);
+            },
+            Instr::jmp_indirect | Instr::call_indirect => {
+                if self.pop(&mut state, function, ValueType::U64, offset) {
+                    self.report(StackErrorKind::UnknownTarget, offset);
+                }
+            },
+
+            instr if reaches(instr) || instr == Instr::drop_bytes => {
+                let count = match decoded.get_operand() {
+                    Some(count) if count.get_mode() == Instr::type_int => count.get_value() as usize,
+                    _ => return self.report(StackErrorKind::UnknownDepth, offset),
+                };
+
+                match instr.value_type() {
+                    Some(value_type) => {
+                        // every value is at least a byte
+                        match count.checked_add(1) {
+                            Some(values) if values <= self.available(&state, function) => (),
+                            _ => return self.report(StackErrorKind::StackUnderflow, offset),
+                        }
+                        for _ in 0..count + 1 {
+                            if !self.pop(&mut state, function, value_type, offset) {
+                                return;
+                            }
+                        }
+                        // the reached values are put back, pick pushes one more
+                        for _ in 0..count + 1 + instr.pushes().len() {
+                            state.shape.push(value_type);
+                        }
+                    },
+                    None => if !self.pop_bytes(&mut state, function, count, offset) {
+                        return;
+                    },
+                }
+                self.propagate(next, state);
+            },
+            instr => {
+                for value_type in instr.pops().iter() {
+                    if !self.pop(&mut state, function, *value_type, offset) {
+                        return;
+                    }
+                }
+                for value_type in instr.pushes().iter() {
+                    state.shape.push(*value_type);
+                }
+                self.propagate(next, state);
+            },
+        }
+    }
+
+    // deepest stack of function and everything it calls, None on recursion
+    fn total_depth(&self, function: usize, visiting: &mut Vector<usize>) -> Option<isize> {
+        if visiting.contains(&function) {
+            return None;
+        }
+        visiting.push(function);
+
+        let mut depth = self.functions[function].max_depth;
+        for &(at, entry) in self.functions[function].calls.iter() {
+            let callee = match self.functions.iter().position(|function| function.entry == entry) {
+                Some(callee) => callee,
+                None => continue,
+            };
+            depth = cmp::max(depth, at.saturating_add(self.total_depth(callee, visiting)?));
+        }
+
+        visiting.pop();
+        Some(depth)
+    }
+}
+
+
+// abstract interpretation of program starting at its first instruction on
+// an empty stack, follows jmp, if_jmp and call targets, relative or not,
+// every called function is summarized by the arguments it pops and the
+// values it leaves at ret, so it can be called at any stack depth
+pub fn analyze(program: &[u8]) -> Analysis {
+    let mut states = Vector::with_capacity(program.len());
+    for _ in 0..program.len() {
+        states.push(None);
+    }
+
+    let mut analyzer = Analyzer {
+        program: program,
+        states: states,
+        functions: Vector::new(),
+        errors: Vector::new(),
+        pending: Vector::new(),
+    };
+    analyzer.function(encoding::read_header(program).1);
+
+    while let Some(offset) = analyzer.pending.pop() {
+        analyzer.transfer(offset);
+    }
+
+    let max_depth = analyzer.total_depth(0, &mut Vector::new());
+    analyzer.errors.sort_unstable_by_key(|error| error.offset);
+
+    Analysis {
+        states: analyzer.states,
+        errors: analyzer.errors,
+        max_depth: max_depth.map(|depth| depth as usize),
+    }
+}
@@ -0,0 +1,468 @@
+use core::fmt;
+
+use vector::Vector;
+use stack::Stack;
+
+use instrs::{Instr, ValueType};
+use decoder::{kind_type, is_marker, is_prefix, has_offset};
+use builder::{ProgramBuilder, OperandValue, BuildError};
+use encoding::Encoding;
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AssembleErrorKind {
+    // mnemonic is not an instruction
+    UnknownInstr,
+    // label definition is not an identifier
+    InvalidLabel,
+    DuplicateLabel,
+    UndefinedLabel,
+    InvalidNumber,
+    // literal does not fit the instruction's type
+    NumberOutOfRange,
+    MissingOperand,
+    UnexpectedToken,
+    // unknown directive or argument, or .encoding after the first instruction
+    InvalidDirective,
+}
+
+impl fmt::Display for AssembleErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AssembleErrorKind::UnknownInstr => write!(f, "unknown instruction"),
+            AssembleErrorKind::InvalidLabel => write!(f, "invalid label"),
+            AssembleErrorKind::DuplicateLabel => write!(f, "duplicate label"),
+            AssembleErrorKind::UndefinedLabel => write!(f, "undefined label"),
+            AssembleErrorKind::InvalidNumber => write!(f, "invalid number"),
+            AssembleErrorKind::NumberOutOfRange => write!(f, "number out of range"),
+            AssembleErrorKind::MissingOperand => write!(f, "missing operand"),
+            AssembleErrorKind::UnexpectedToken => write!(f, "unexpected token"),
+            AssembleErrorKind::InvalidDirective => write!(f, "invalid directive"),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct AssembleError {
+    kind: AssembleErrorKind,
+    line: usize,
+    column: usize,
+}
+
+impl AssembleError {
+
+    #[inline]
+    pub fn new(kind: AssembleErrorKind, line: usize, column: usize) -> Self {
+        AssembleError {
+            kind: kind,
+            line: line,
+            column: column,
+        }
+    }
+
+    #[inline]
+    pub fn get_kind(&self) -> AssembleErrorKind {self.kind}
+    // 1 based
+    #[inline]
+    pub fn get_line(&self) -> usize {self.line}
+    // 1 based
+    #[inline]
+    pub fn get_column(&self) -> usize {self.column}
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+
+#[inline]
+fn mode_from_keyword(keyword: &str) -> Option<Instr> {
+    match keyword {
+        "int" => Some(Instr::type_int),
+        "ptr" => Some(Instr::type_ptr),
+        "ptr_off" => Some(Instr::type_ptr_off),
+        "idr_ptr" => Some(Instr::type_idr_ptr),
+        "idr_ptr_off" => Some(Instr::type_idr_ptr_off),
+        _ => None,
+    }
+}
+
+#[inline]
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    match chars.next() {
+        Some(ch) if ch.is_ascii_alphabetic() || ch == '_' => (),
+        _ => return false,
+    }
+    chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+
+// splits a line into whitespace or comma separated tokens with their
+// 1 based column, stops at // # or ; comments
+struct Tokens<'a> {
+    line: &'a str,
+    index: usize,
+}
+
+impl<'a> Tokens<'a> {
+    #[inline]
+    fn new(line: &'a str) -> Self {
+        Tokens {
+            line: line,
+            index: 0,
+        }
+    }
+    #[inline]
+    fn end_column(&self) -> usize {self.line.len() + 1}
+}
+
+#[inline]
+fn is_separator(byte: u8) -> bool {
+    byte == b',' || byte == b' ' || byte == b'\t' || byte == b'\r'
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (&'a str, usize);
+
+    fn next(&mut self) -> Option<(&'a str, usize)> {
+        let bytes = self.line.as_bytes();
+
+        while self.index < bytes.len() && is_separator(bytes[self.index]) {
+            self.index += 1;
+        }
+
+        let rest = &self.line[self.index..];
+        if rest.is_empty() || rest.starts_with("//") || rest.starts_with('#') || rest.starts_with(';') {
+            self.index = bytes.len();
+            return None;
+        }
+
+        let start = self.index;
+        while self.index < bytes.len() && !is_separator(bytes[self.index]) {
+            self.index += 1;
+        }
+        Some((&self.line[start..self.index], start + 1))
+    }
+}
+
+
+#[derive(Debug, Clone, Copy)]
+enum Value<'a> {
+    Bits(u64),
+    // name, line, column
+    Label(&'a str, usize, usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Operand<'a> {
+    // type_* prefix
+    mode: Instr,
+    value: Value<'a>,
+    // second value of type_ptr_off and type_idr_ptr_off
+    offset: u64,
+}
+
+#[derive(Debug)]
+struct Statement<'a> {
+    instr: Instr,
+    // one per instr.operands()
+    operands: Vector<Operand<'a>>,
+}
+
+#[derive(Debug)]
+struct Label<'a> {
+    name: &'a str,
+    // index of the statement the label points at
+    index: usize,
+}
+
+
+#[inline]
+fn max_unsigned(bits: u32) -> u128 {(1u128 << bits) - 1}
+
+fn parse_magnitude(text: &str) -> Result<u128, AssembleErrorKind> {
+    let (digits, radix) = if let Some(digits) = text.strip_prefix("0x") {
+        (digits, 16)
+    } else if let Some(digits) = text.strip_prefix("0o") {
+        (digits, 8)
+    } else if let Some(digits) = text.strip_prefix("0b") {
+        (digits, 2)
+    } else {
+        (text, 10)
+    };
+
+    if digits.is_empty() || digits.starts_with('+') || digits.starts_with('-') {
+        return Err(AssembleErrorKind::InvalidNumber);
+    }
+    match u128::from_str_radix(digits, radix) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(AssembleErrorKind::InvalidNumber),
+    }
+}
+
+// parses a literal of value_type into its bit pattern, a 0x float literal
+// is the bit pattern itself
+fn parse_number(text: &str, value_type: ValueType) -> Result<u64, AssembleErrorKind> {
+    let bits = value_type.bits();
+
+    match value_type {
+        ValueType::U8 | ValueType::U16 | ValueType::U32 | ValueType::U64 => {
+            let value = parse_magnitude(text)?;
+
+            if value > max_unsigned(bits) {
+                Err(AssembleErrorKind::NumberOutOfRange)
+            } else {
+                Ok(value as u64)
+            }
+        },
+        ValueType::I8 | ValueType::I16 | ValueType::I32 | ValueType::I64 => {
+            let negative = text.starts_with('-');
+            let magnitude = parse_magnitude(if negative {&text[1..]} else {text})?;
+            let limit = 1u128 << (bits - 1);
+
+            if (negative && magnitude > limit) || (!negative && magnitude >= limit) {
+                Err(AssembleErrorKind::NumberOutOfRange)
+            } else {
+                let value = if negative {(magnitude as i128).wrapping_neg()} else {magnitude as i128};
+                Ok((value as u128 & max_unsigned(bits)) as u64)
+            }
+        },
+        ValueType::F32 | ValueType::F64 if text.starts_with("0x") => {
+            let value = parse_magnitude(text)?;
+
+            if value > max_unsigned(bits) {
+                Err(AssembleErrorKind::NumberOutOfRange)
+            } else {
+                Ok(value as u64)
+            }
+        },
+        ValueType::F32 => match text.parse::<f32>() {
+            Ok(value) => Ok(value.to_bits() as u64),
+            Err(_) => Err(AssembleErrorKind::InvalidNumber),
+        },
+        ValueType::F64 => match text.parse::<f64>() {
+            Ok(value) => Ok(value.to_bits()),
+            Err(_) => Err(AssembleErrorKind::InvalidNumber),
+        },
+    }
+}
+
+
+fn parse_operand<'a>(
+    tokens: &mut Tokens<'a>, value_type: ValueType, line: usize, column: usize
+) -> Result<Operand<'a>, AssembleError> {
+    let (mut text, mut column) = match tokens.next() {
+        Some(token) => token,
+        None => return Err(AssembleError::new(AssembleErrorKind::MissingOperand, line, column)),
+    };
+
+    let mode = match mode_from_keyword(text) {
+        Some(mode) => {
+            let end_column = tokens.end_column();
+            let (next_text, next_column) = match tokens.next() {
+                Some(token) => token,
+                None => return Err(AssembleError::new(AssembleErrorKind::MissingOperand, line, end_column)),
+            };
+            text = next_text;
+            column = next_column;
+            mode
+        },
+        None => Instr::type_int,
+    };
+
+    let value = if mode == Instr::type_int && value_type.is_integer() && is_identifier(text) {
+        Value::Label(text, line, column)
+    } else {
+        // addresses are read as usize
+        let value_type = if mode == Instr::type_int {value_type} else {ValueType::U64};
+
+        match parse_number(text, value_type) {
+            Ok(bits) => Value::Bits(bits),
+            Err(kind) => return Err(AssembleError::new(kind, line, column)),
+        }
+    };
+
+    let offset = if has_offset(mode) {
+        let end_column = tokens.end_column();
+        match tokens.next() {
+            Some((text, column)) => match parse_number(text, ValueType::U64) {
+                Ok(bits) => bits,
+                Err(kind) => return Err(AssembleError::new(kind, line, column)),
+            },
+            None => return Err(AssembleError::new(AssembleErrorKind::MissingOperand, line, end_column)),
+        }
+    } else {
+        0
+    };
+
+    Ok(Operand {
+        mode: mode,
+        value: value,
+        offset: offset,
+    })
+}
+
+// `.encoding fixed` or `.encoding varint`, before the first instruction
+fn parse_directive(
+    tokens: &mut Tokens, text: &str, line: usize, column: usize,
+    statements: &Vector<Statement>, encoding: &mut Option<Encoding>
+) -> Result<(), AssembleError> {
+    if text != ".encoding" || encoding.is_some() || !statements.is_empty() {
+        return Err(AssembleError::new(AssembleErrorKind::InvalidDirective, line, column));
+    }
+
+    let end_column = tokens.end_column();
+    *encoding = match tokens.next() {
+        Some(("fixed", _)) => Some(Encoding::Fixed),
+        Some(("varint", _)) => Some(Encoding::Varint),
+        Some((_, column)) => return Err(AssembleError::new(AssembleErrorKind::InvalidDirective, line, column)),
+        None => return Err(AssembleError::new(AssembleErrorKind::MissingOperand, line, end_column)),
+    };
+
+    match tokens.next() {
+        Some((_, column)) => Err(AssembleError::new(AssembleErrorKind::UnexpectedToken, line, column)),
+        None => Ok(()),
+    }
+}
+
+fn parse_line<'a>(
+    source: &'a str, line: usize, statements: &mut Vector<Statement<'a>>, labels: &mut Vector<Label<'a>>,
+    encoding: &mut Option<Encoding>
+) -> Result<(), AssembleError> {
+    let mut tokens = Tokens::new(source);
+    let mut token = tokens.next();
+
+    if let Some((text, column)) = token {
+        if text.starts_with('.') {
+            return parse_directive(&mut tokens, text, line, column, statements, encoding);
+        }
+    }
+
+    // label definitions
+    while let Some((text, column)) = token {
+        if !text.ends_with(':') {
+            break;
+        }
+        let name = &text[..text.len() - 1];
+
+        if !is_identifier(name) || mode_from_keyword(name).is_some() {
+            return Err(AssembleError::new(AssembleErrorKind::InvalidLabel, line, column));
+        }
+        if labels.iter().any(|label| label.name == name) {
+            return Err(AssembleError::new(AssembleErrorKind::DuplicateLabel, line, column));
+        }
+        labels.push(Label {
+            name: name,
+            index: statements.len(),
+        });
+        token = tokens.next();
+    }
+
+    // instruction, checked and sat may be followed by the instruction they prefix
+    while let Some((text, column)) = token {
+        let instr = match Instr::from_mnemonic(text) {
+            Some(instr) if !is_marker(instr) => instr,
+            _ => return Err(AssembleError::new(AssembleErrorKind::UnknownInstr, line, column)),
+        };
+
+        let mut operands = Vector::new();
+        for kind in instr.operands().iter() {
+            operands.push(parse_operand(&mut tokens, kind_type(instr, *kind), line, column)?);
+        }
+
+        statements.push(Statement {
+            instr: instr,
+            operands: operands,
+        });
+
+        token = if is_prefix(instr) {
+            tokens.next()
+        } else {
+            match tokens.next() {
+                Some((_, column)) => return Err(AssembleError::new(AssembleErrorKind::UnexpectedToken, line, column)),
+                None => None,
+            }
+        };
+    }
+
+    Ok(())
+}
+
+
+// assembles source into a program runnable by Process::new
+//
+// one instruction per line, mnemonics are the Instr names, operands are
+// separated by whitespace or commas, `name:` defines a label and jmp,
+// if_jmp, call, their _rel variants and integer push_* accept a label in
+// place of a number, ptr, ptr_off, idr_ptr and idr_ptr_off select the
+// operand type, a leading `.encoding fixed` or `.encoding varint` writes
+// a program header
+pub fn assemble(source: &str) -> Result<Vector<u8>, AssembleError> {
+    let mut statements = Vector::new();
+    let mut labels = Vector::new();
+    let mut header = None;
+
+    for (index, line) in source.lines().enumerate() {
+        parse_line(line, index + 1, &mut statements, &mut labels, &mut header)?;
+    }
+
+    lower(&statements, &labels, header)
+}
+
+// adds the statements to a ProgramBuilder, which picks the operand sizes
+fn lower(statements: &Vector<Statement>, labels: &Vector<Label>, header: Option<Encoding>) -> Result<Vector<u8>, AssembleError> {
+    let mut builder = match header {
+        Some(encoding) => ProgramBuilder::with_encoding(encoding),
+        None => ProgramBuilder::new(),
+    };
+    let mut targets = Vector::with_capacity(labels.len());
+    for _ in labels.iter() {
+        targets.push(builder.label());
+    }
+
+    // labels are defined in statement order
+    let mut next = 0;
+    for (index, statement) in statements.iter().enumerate() {
+        while next < labels.len() && labels[next].index == index {
+            builder.bind(targets[next]);
+            next += 1;
+        }
+
+        let mut values = Vector::with_capacity(statement.operands.len());
+        for operand in statement.operands.iter() {
+            values.push(match operand.value {
+                Value::Bits(bits) => OperandValue::Bits(operand.mode, bits, operand.offset),
+                Value::Label(name, line, column) => match labels.iter().position(|label| label.name == name) {
+                    Some(label) => OperandValue::Label(targets[label]),
+                    None => return Err(AssembleError::new(AssembleErrorKind::UndefinedLabel, line, column)),
+                },
+            });
+        }
+        builder.with_operands(statement.instr, &values);
+    }
+    // the ones after the last statement point at the end of the program
+    for target in targets[next..].iter() {
+        builder.bind(*target);
+    }
+
+    match builder.build() {
+        Ok(program) => Ok(program),
+        Err(BuildError::LabelOutOfRange(_, index)) => {
+            let (line, column) = statements[index].operands.iter()
+                .filter_map(|operand| match operand.value {
+                    Value::Label(_, line, column) => Some((line, column)),
+                    Value::Bits(_) => None,
+                })
+                .next()
+                .unwrap_or((0, 0));
+            Err(AssembleError::new(AssembleErrorKind::NumberOutOfRange, line, column))
+        },
+        // labels are bound once and every statement has its operands
+        Err(error) => unreachable!("{}", error),
+    }
+}
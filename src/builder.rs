@@ -0,0 +1,650 @@
+use core::fmt;
+
+use vector::Vector;
+use stack::Stack;
+
+use instrs::{Instr, ValueType};
+use decoder::{Decoded, kind_type, has_offset, is_branch, is_relative};
+use encoding::{self, Encoding, HEADER_LEN, zigzag, sign_extend, varint_len, emit_varint};
+
+
+// smallest size_* payload that holds bits
+#[inline]
+pub fn bits_size(bits: u64) -> usize {
+    if bits <= 0xff {
+        1
+    } else if bits <= 0xffff {
+        2
+    } else if bits <= 0xffff_ffff {
+        4
+    } else {
+        8
+    }
+}
+
+// writes the opcode, prefixed by ext for extended instructions
+#[inline]
+pub fn emit_instr(program: &mut Vector<u8>, instr: Instr) {
+    program.push(instr.opcode());
+    if let Some(opcode) = instr.extended_opcode() {
+        program.push(opcode);
+    }
+}
+
+// writes the size_* prefix and the big endian payload
+#[inline]
+pub fn emit_sized(program: &mut Vector<u8>, bits: u64, size: usize) {
+    program.push(match size {
+        1 => Instr::size_8,
+        2 => Instr::size_16,
+        4 => Instr::size_32,
+        _ => Instr::size_64,
+    } as u8);
+
+    for i in (0..size).rev() {
+        program.push((bits >> (i * 8)) as u8);
+    }
+}
+
+// bits of an operand value as written in the varint encoding, signed
+// type_int immediates are zigzag encoded
+#[inline]
+fn varint_bits(bits: u64, mode: Instr, value_type: ValueType) -> u64 {
+    if mode == Instr::type_int && value_type.is_signed() {
+        zigzag(sign_extend(bits, value_type))
+    } else {
+        bits
+    }
+}
+
+// smallest encoded size of an operand value, including its size_* prefix
+#[inline]
+pub fn value_size(encoding: Encoding, bits: u64, mode: Instr, value_type: ValueType) -> usize {
+    match encoding {
+        Encoding::Fixed => 1 + bits_size(bits),
+        Encoding::Varint => varint_len(varint_bits(bits, mode, value_type)),
+    }
+}
+
+// writes an operand value in size bytes, size is at least its value_size
+#[inline]
+pub fn emit_value(program: &mut Vector<u8>, encoding: Encoding, bits: u64, size: usize, mode: Instr, value_type: ValueType) {
+    match encoding {
+        Encoding::Fixed => emit_sized(program, bits, size - 1),
+        Encoding::Varint => emit_varint(program, varint_bits(bits, mode, value_type), size),
+    }
+}
+
+// operand bits of a label at address used by the instruction at offset,
+// relative instructions take the i32 distance between them
+#[inline]
+pub fn label_bits(instr: Instr, address: usize, offset: usize) -> u64 {
+    if is_relative(instr) {
+        address.wrapping_sub(offset) as u32 as u64
+    } else {
+        address as u64
+    }
+}
+
+// whether a label at address fits the operand of the instruction at offset,
+// relative instructions take the i32 distance between them
+#[inline]
+pub fn label_fits(instr: Instr, value_type: ValueType, address: usize, offset: usize) -> bool {
+    if is_relative(instr) {
+        let distance = address as i64 - offset as i64;
+        distance >= i64::from(i32::MIN) && distance <= i64::from(i32::MAX)
+    } else {
+        value_type.bits() >= 64 || (address as u64) >> value_type.bits() == 0
+    }
+}
+
+
+// a position in the program, created unbound and bound once
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Label(usize);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BuildError {
+    // label is used but never bound
+    UnboundLabel(Label),
+    // label is bound more than once
+    LabelBoundTwice(Label),
+    // label was created by another builder
+    UnknownLabel(Label),
+    // instruction is given a different number of operands than it takes
+    OperandCount(Instr),
+    // address of label does not fit the operand of the instruction at
+    // index, counting instructions in the order they were added
+    LabelOutOfRange(Label, usize),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::UnboundLabel(Label(index)) => write!(f, "label {} is never bound", index),
+            BuildError::LabelBoundTwice(Label(index)) => write!(f, "label {} is bound twice", index),
+            BuildError::UnknownLabel(Label(index)) => write!(f, "label {} is not from this builder", index),
+            BuildError::OperandCount(instr) => write!(f, "{} takes {} operands", instr.mnemonic(), instr.operands().len()),
+            BuildError::LabelOutOfRange(Label(label), index) => write!(f, "label {} does not fit instruction {}", label, index),
+        }
+    }
+}
+
+
+// operand given to with_operands
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OperandValue {
+    // type_* mode, value and the offset of type_ptr_off and type_idr_ptr_off
+    Bits(Instr, u64, u64),
+    // address of label as a type_int value, or its offset from the
+    // instruction for the relative branches
+    Label(Label),
+}
+
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Bits(u64),
+    Label(Label),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Operand {
+    mode: Instr,
+    value: Value,
+    offset: u64,
+    // type the operand is read as
+    value_type: ValueType,
+    // encoded bytes of a label value, grown until the address fits
+    label_size: usize,
+}
+
+#[derive(Debug)]
+struct Statement {
+    instr: Instr,
+    // one per instr.operands()
+    operands: Vector<Operand>,
+}
+
+
+macro_rules! simple {
+    ($($name: ident,)*) => {
+        $(
+            #[inline]
+            pub fn $name(&mut self) -> &mut Self {self.instr(Instr::$name)}
+        )*
+    };
+}
+
+macro_rules! local {
+    ($($name: ident,)*) => {
+        $(
+            #[inline]
+            pub fn $name(&mut self, offset: usize) -> &mut Self {
+                self.with_operand(Instr::$name, Instr::type_int, offset as u64, 0)
+            }
+        )*
+    };
+}
+
+macro_rules! branch {
+    ($($name: ident,)*) => {
+        $(
+            #[inline]
+            pub fn $name(&mut self, label: Label) -> &mut Self {self.with_label(Instr::$name, label)}
+        )*
+    };
+}
+
+macro_rules! depth {
+    ($($name: ident,)*) => {
+        $(
+            #[inline]
+            pub fn $name(&mut self, depth: usize) -> &mut Self {
+                self.with_operand(Instr::$name, Instr::type_int, depth as u64, 0)
+            }
+        )*
+    };
+}
+
+macro_rules! push {
+    ($($name: ident: $ty: ty => $bits: expr,)*) => {
+        $(
+            #[inline]
+            pub fn $name(&mut self, value: $ty) -> &mut Self {
+                let bits = $bits(value);
+                self.with_operand(Instr::$name, Instr::type_int, bits, 0)
+            }
+        )*
+    };
+}
+
+
+// emits bytecode instruction by instruction
+//
+// let mut builder = ProgramBuilder::new();
+// let end = builder.label();
+// builder.push_u8(1).if_jmp(end).nop();
+// builder.bind(end).halt();
+// let program = builder.build()?;
+pub struct ProgramBuilder {
+    statements: Vector<Statement>,
+    // statement index each label is bound to
+    labels: Vector<Option<usize>>,
+    error: Option<BuildError>,
+    // written as the program header, None for a headerless program
+    encoding: Option<Encoding>,
+}
+
+impl Default for ProgramBuilder {
+    #[inline]
+    fn default() -> Self {Self::new()}
+}
+
+impl ProgramBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        ProgramBuilder {
+            statements: Vector::new(),
+            labels: Vector::new(),
+            error: None,
+            encoding: None,
+        }
+    }
+    // builds a program starting with a header selecting encoding
+    #[inline]
+    pub fn with_encoding(encoding: Encoding) -> Self {
+        let mut builder = Self::new();
+        builder.encoding = Some(encoding);
+        builder
+    }
+
+    #[inline]
+    pub fn label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+    // binds label to the next instruction
+    #[inline]
+    pub fn bind(&mut self, label: Label) -> &mut Self {
+        let index = self.statements.len();
+
+        match self.labels.get(label.0) {
+            Some(&Some(_)) => self.fail(BuildError::LabelBoundTwice(label)),
+            Some(&None) => self.labels[label.0] = Some(index),
+            None => self.fail(BuildError::UnknownLabel(label)),
+        }
+        self
+    }
+
+    // keeps the first error, build returns it
+    #[inline]
+    fn fail(&mut self, error: BuildError) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    // operand number index of instr
+    #[inline]
+    fn operand(instr: Instr, index: usize, mode: Instr, value: Value, offset: u64) -> Operand {
+        let value_type = match instr.operands().get(index) {
+            Some(kind) => kind_type(instr, *kind),
+            None => ValueType::U64,
+        };
+
+        Operand {
+            mode: mode,
+            value: value,
+            offset: offset,
+            value_type: value_type,
+            label_size: 1,
+        }
+    }
+    #[inline]
+    fn statement(&mut self, instr: Instr, operands: &[Operand]) -> &mut Self {
+        let mut statement = Statement {
+            instr: instr,
+            operands: Vector::with_capacity(operands.len()),
+        };
+        for operand in operands.iter() {
+            statement.operands.push(*operand);
+        }
+        self.statements.push(statement);
+        self
+    }
+
+    // instruction without an operand
+    #[inline]
+    pub fn instr(&mut self, instr: Instr) -> &mut Self {self.with_operands(instr, &[])}
+    // instruction with a type_* operand, offset is only encoded for
+    // type_ptr_off and type_idr_ptr_off
+    #[inline]
+    pub fn with_operand(&mut self, instr: Instr, mode: Instr, value: u64, offset: u64) -> &mut Self {
+        self.with_operands(instr, &[OperandValue::Bits(mode, value, offset)])
+    }
+    // instruction with the address of label as its type_int operand, or
+    // its offset from the instruction for the relative branches
+    #[inline]
+    pub fn with_label(&mut self, instr: Instr, label: Label) -> &mut Self {
+        self.with_operands(instr, &[OperandValue::Label(label)])
+    }
+    // instruction with one value per instr.operands(), any other number is
+    // an OperandCount error and nothing is added
+    pub fn with_operands(&mut self, instr: Instr, values: &[OperandValue]) -> &mut Self {
+        if values.len() != instr.operands().len() {
+            self.fail(BuildError::OperandCount(instr));
+            return self;
+        }
+        let mut operands = Vector::with_capacity(values.len());
+
+        for (index, value) in values.iter().enumerate() {
+            operands.push(match *value {
+                OperandValue::Bits(mode, bits, offset) => Self::operand(instr, index, mode, Value::Bits(bits), offset),
+                OperandValue::Label(label) => Self::operand(instr, index, Instr::type_int, Value::Label(label), 0),
+            });
+        }
+        self.statement(instr, &operands)
+    }
+    // instruction as decoded, target takes the place of its type_int
+    // branch operand
+    pub fn with_decoded(&mut self, decoded: &Decoded, target: Option<Label>) -> &mut Self {
+        let instr = decoded.get_instr();
+        let mut operands = Vector::with_capacity(decoded.get_operands().len());
+
+        for (index, operand) in decoded.get_operands().iter().enumerate() {
+            let value = match target {
+                Some(label) if index == 0 && is_branch(instr) && operand.get_mode() == Instr::type_int => Value::Label(label),
+                _ => Value::Bits(operand.get_value()),
+            };
+            operands.push(Self::operand(instr, index, operand.get_mode(), value, operand.get_offset()));
+        }
+        self.statement(instr, &operands)
+    }
+
+    #[inline]
+    pub fn jmp(&mut self, label: Label) -> &mut Self {self.with_label(Instr::jmp, label)}
+    #[inline]
+    pub fn if_jmp(&mut self, label: Label) -> &mut Self {self.with_label(Instr::if_jmp, label)}
+    #[inline]
+    pub fn call(&mut self, label: Label) -> &mut Self {self.with_label(Instr::call, label)}
+
+    #[inline]
+    pub fn jmp_rel(&mut self, label: Label) -> &mut Self {self.with_label(Instr::jmp_rel, label)}
+    #[inline]
+    pub fn if_jmp_rel(&mut self, label: Label) -> &mut Self {self.with_label(Instr::if_jmp_rel, label)}
+    #[inline]
+    pub fn call_rel(&mut self, label: Label) -> &mut Self {self.with_label(Instr::call_rel, label)}
+
+    branch! {
+        br_eq_u8, br_eq_u16, br_eq_u32, br_eq_u64,
+        br_eq_i8, br_eq_i16, br_eq_i32, br_eq_i64, br_eq_f32, br_eq_f64,
+
+        br_lt_u8, br_lt_u16, br_lt_u32, br_lt_u64,
+        br_lt_i8, br_lt_i16, br_lt_i32, br_lt_i64, br_lt_f32, br_lt_f64,
+
+        br_zero_u8, br_zero_u16, br_zero_u32, br_zero_u64,
+        br_zero_i8, br_zero_i16, br_zero_i32, br_zero_i64, br_zero_f32, br_zero_f64,
+
+        br_nonzero_u8, br_nonzero_u16, br_nonzero_u32, br_nonzero_u64,
+        br_nonzero_i8, br_nonzero_i16, br_nonzero_i32, br_nonzero_i64, br_nonzero_f32, br_nonzero_f64,
+    }
+
+    // call moving the top args bytes into the callee's frame
+    #[inline]
+    pub fn call_args(&mut self, label: Label, args: usize) -> &mut Self {
+        self.with_operands(Instr::call_args, &[
+            OperandValue::Label(label),
+            OperandValue::Bits(Instr::type_int, args as u64, 0),
+        ])
+    }
+    // starts a process at label with the top args bytes as its stack
+    #[inline]
+    pub fn spawn(&mut self, label: Label, args: usize) -> &mut Self {
+        self.with_operands(Instr::spawn, &[
+            OperandValue::Label(label),
+            OperandValue::Bits(Instr::type_int, args as u64, 0),
+        ])
+    }
+    #[inline]
+    pub fn enter(&mut self, locals: usize) -> &mut Self {
+        self.with_operand(Instr::enter, Instr::type_int, locals as u64, 0)
+    }
+
+    local! {
+        local_get_u8, local_get_u16, local_get_u32, local_get_u64,
+        local_get_i8, local_get_i16, local_get_i32, local_get_i64, local_get_f32, local_get_f64,
+
+        local_set_u8, local_set_u16, local_set_u32, local_set_u64,
+        local_set_i8, local_set_i16, local_set_i32, local_set_i64, local_set_f32, local_set_f64,
+    }
+
+    depth! {
+        pick_u8, pick_u16, pick_u32, pick_u64,
+        pick_i8, pick_i16, pick_i32, pick_i64, pick_f32, pick_f64,
+
+        roll_u8, roll_u16, roll_u32, roll_u64,
+        roll_i8, roll_i16, roll_i32, roll_i64, roll_f32, roll_f64,
+    }
+    #[inline]
+    pub fn drop_bytes(&mut self, count: usize) -> &mut Self {
+        self.with_operand(Instr::drop_bytes, Instr::type_int, count as u64, 0)
+    }
+
+    push! {
+        push_u8: u8 => |value: u8| value as u64,
+        push_u16: u16 => |value: u16| value as u64,
+        push_u32: u32 => |value: u32| value as u64,
+        push_u64: u64 => |value: u64| value,
+        push_i8: i8 => |value: i8| value as u8 as u64,
+        push_i16: i16 => |value: i16| value as u16 as u64,
+        push_i32: i32 => |value: i32| value as u32 as u64,
+        push_i64: i64 => |value: i64| value as u64,
+        push_f32: f32 => |value: f32| value.to_bits() as u64,
+        push_f64: f64 => |value: f64| value.to_bits(),
+    }
+
+    simple! {
+        nop, halt, ret,
+
+        pop_u8, pop_u16, pop_u32, pop_u64,
+        pop_i8, pop_i16, pop_i32, pop_i64, pop_f32, pop_f64,
+
+        copy_u8, copy_u16, copy_u32, copy_u64,
+        copy_i8, copy_i16, copy_i32, copy_i64, copy_f32, copy_f64,
+
+        swap_u8, swap_u16, swap_u32, swap_u64,
+        swap_i8, swap_i16, swap_i32, swap_i64, swap_f32, swap_f64,
+
+        over_u8, over_u16, over_u32, over_u64,
+        over_i8, over_i16, over_i32, over_i64, over_f32, over_f64,
+
+        rot_u8, rot_u16, rot_u32, rot_u64,
+        rot_i8, rot_i16, rot_i32, rot_i64, rot_f32, rot_f64,
+
+        load_u8, load_u16, load_u32, load_u64,
+        load_i8, load_i16, load_i32, load_i64, load_f32, load_f64,
+
+        save_u8, save_u16, save_u32, save_u64,
+        save_i8, save_i16, save_i32, save_i64, save_f32, save_f64,
+
+        add_u8, add_u16, add_u32, add_u64,
+        add_i8, add_i16, add_i32, add_i64, add_f32, add_f64,
+
+        sub_u8, sub_u16, sub_u32, sub_u64,
+        sub_i8, sub_i16, sub_i32, sub_i64, sub_f32, sub_f64,
+
+        mul_u8, mul_u16, mul_u32, mul_u64,
+        mul_i8, mul_i16, mul_i32, mul_i64, mul_f32, mul_f64,
+
+        div_u8, div_u16, div_u32, div_u64,
+        div_i8, div_i16, div_i32, div_i64, div_f32, div_f64,
+
+        rem_u8, rem_u16, rem_u32, rem_u64,
+        rem_i8, rem_i16, rem_i32, rem_i64, rem_f32, rem_f64,
+
+        and_u8, and_u16, and_u32, and_u64,
+        and_i8, and_i16, and_i32, and_i64,
+
+        or_u8, or_u16, or_u32, or_u64,
+        or_i8, or_i16, or_i32, or_i64,
+
+        xor_u8, xor_u16, xor_u32, xor_u64,
+        xor_i8, xor_i16, xor_i32, xor_i64,
+
+        shl_u8, shl_u16, shl_u32, shl_u64,
+        shl_i8, shl_i16, shl_i32, shl_i64,
+
+        shr_u8, shr_u16, shr_u32, shr_u64,
+        shr_i8, shr_i16, shr_i32, shr_i64,
+
+        not_u8, not_u16, not_u32, not_u64,
+        not_i8, not_i16, not_i32, not_i64,
+
+        neg_u8, neg_u16, neg_u32, neg_u64,
+        neg_i8, neg_i16, neg_i32, neg_i64, neg_f32, neg_f64,
+
+        lt_u8, lt_u16, lt_u32, lt_u64,
+        lt_i8, lt_i16, lt_i32, lt_i64, lt_f32, lt_f64,
+
+        lte_u8, lte_u16, lte_u32, lte_u64,
+        lte_i8, lte_i16, lte_i32, lte_i64, lte_f32, lte_f64,
+
+        gt_u8, gt_u16, gt_u32, gt_u64,
+        gt_i8, gt_i16, gt_i32, gt_i64, gt_f32, gt_f64,
+
+        gte_u8, gte_u16, gte_u32, gte_u64,
+        gte_i8, gte_i16, gte_i32, gte_i64, gte_f32, gte_f64,
+
+        eq_u8, eq_u16, eq_u32, eq_u64,
+        eq_i8, eq_i16, eq_i32, eq_i64, eq_f32, eq_f64,
+
+        neq_u8, neq_u16, neq_u32, neq_u64,
+        neq_i8, neq_i16, neq_i32, neq_i64, neq_f32, neq_f64,
+
+        ext_u8, ext_u16, ext_u32,
+        ext_i8, ext_i16, ext_i32,
+        wrap_u8, wrap_u16, wrap_u32,
+        cvt_i64_f32, cvt_u64_f32, cvt_i64_f64, cvt_u64_f64,
+        cvt_f32_i64, cvt_f32_u64, cvt_f64_i64, cvt_f64_u64,
+        promote_f32, demote_f64,
+
+        checked, sat,
+
+        halt_with, pause, wait,
+
+        abs_f32, abs_f64, min_f32, min_f64, max_f32, max_f64,
+
+        jmp_indirect, call_indirect,
+    }
+
+    #[inline]
+    fn resolve(&self, label: Label) -> Result<usize, BuildError> {
+        match self.labels.get(label.0) {
+            Some(&Some(index)) => Ok(index),
+            Some(&None) => Err(BuildError::UnboundLabel(label)),
+            None => Err(BuildError::UnknownLabel(label)),
+        }
+    }
+
+    #[inline]
+    fn get_encoding(&self) -> Encoding {self.encoding.unwrap_or(Encoding::Fixed)}
+
+    // offset of every statement and of the end of the program
+    fn layout(&self) -> Vector<usize> {
+        let encoding = self.get_encoding();
+        let mut offsets = Vector::new();
+        let mut offset = if self.encoding.is_some() {HEADER_LEN} else {0};
+
+        for statement in self.statements.iter() {
+            offsets.push(offset);
+            offset += statement.instr.opcode_len();
+
+            for operand in statement.operands.iter() {
+                offset += 1 + match operand.value {
+                    Value::Bits(bits) => value_size(encoding, bits, operand.mode, operand.value_type),
+                    Value::Label(_) => operand.label_size,
+                };
+                if has_offset(operand.mode) {
+                    offset += value_size(encoding, operand.offset, operand.mode, ValueType::U64);
+                }
+            }
+        }
+        offsets.push(offset);
+
+        offsets
+    }
+
+    // encodes the program with the smallest size for every operand,
+    // label operands grow until every address fits
+    pub fn build(&mut self) -> Result<Vector<u8>, BuildError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let encoding = self.get_encoding();
+
+        let mut offsets;
+        loop {
+            offsets = self.layout();
+            let mut grown = false;
+
+            for index in 0..self.statements.len() {
+                let instr = self.statements[index].instr;
+
+                for number in 0..self.statements[index].operands.len() {
+                    let operand = self.statements[index].operands[number];
+
+                    if let Value::Label(label) = operand.value {
+                        let bits = label_bits(instr, offsets[self.resolve(label)?], offsets[index]);
+                        let size = value_size(encoding, bits, operand.mode, operand.value_type);
+
+                        if size > operand.label_size {
+                            self.statements[index].operands[number].label_size = size;
+                            grown = true;
+                        }
+                    }
+                }
+            }
+
+            if !grown {
+                break;
+            }
+        }
+
+        let mut program = Vector::new();
+        if let Some(encoding) = self.encoding {
+            encoding::emit_header(&mut program, encoding);
+        }
+
+        for (index, statement) in self.statements.iter().enumerate() {
+            emit_instr(&mut program, statement.instr);
+
+            for operand in statement.operands.iter() {
+                let value_type = operand.value_type;
+                program.push(operand.mode as u8);
+
+                match operand.value {
+                    Value::Bits(bits) => {
+                        let size = value_size(encoding, bits, operand.mode, value_type);
+                        emit_value(&mut program, encoding, bits, size, operand.mode, value_type);
+                    },
+                    Value::Label(label) => {
+                        let address = offsets[self.resolve(label)?];
+                        if !label_fits(statement.instr, value_type, address, offsets[index]) {
+                            return Err(BuildError::LabelOutOfRange(label, index));
+                        }
+                        let bits = label_bits(statement.instr, address, offsets[index]);
+                        emit_value(&mut program, encoding, bits, operand.label_size, operand.mode, value_type);
+                    },
+                }
+
+                if has_offset(operand.mode) {
+                    let size = value_size(encoding, operand.offset, operand.mode, ValueType::U64);
+                    emit_value(&mut program, encoding, operand.offset, size, operand.mode, ValueType::U64);
+                }
+            }
+        }
+
+        Ok(program)
+    }
+}
@@ -0,0 +1,301 @@
+use core::convert::TryFrom;
+use core::fmt;
+
+use instrs::{Instr, ValueType, OperandKind};
+use encoding::{self, Encoding, VarintError};
+
+
+// type an operand of kind is read as by instr
+#[inline]
+pub fn kind_type(instr: Instr, kind: OperandKind) -> ValueType {
+    match kind {
+        // program offsets and byte counts are read as usize
+        OperandKind::Target | OperandKind::Count => ValueType::U64,
+        OperandKind::Relative => ValueType::I32,
+        OperandKind::Immediate => instr.value_type().unwrap_or(ValueType::U64),
+    }
+}
+
+// operand type_* and size_* prefixes and the ext escape, never an
+// instruction on their own
+#[inline]
+pub fn is_marker(instr: Instr) -> bool {
+    matches!(instr,
+        Instr::type_int | Instr::type_ptr | Instr::type_ptr_off |
+        Instr::type_idr_ptr | Instr::type_idr_ptr_off |
+        Instr::size_8 | Instr::size_16 | Instr::size_32 | Instr::size_64 |
+        Instr::ext)
+}
+
+// checked and sat, prefix the next instruction
+#[inline]
+pub fn is_prefix(instr: Instr) -> bool {
+    instr == Instr::checked || instr == Instr::sat
+}
+
+// jmp, if_jmp and call and their relative variants and spawn, their operand
+// is a program offset
+#[inline]
+pub fn is_branch(instr: Instr) -> bool {
+    matches!(instr.operands().first(), Some(&OperandKind::Target) | Some(&OperandKind::Relative))
+}
+
+// jmp_rel, if_jmp_rel and call_rel, their operand is relative to the instruction
+#[inline]
+pub fn is_relative(instr: Instr) -> bool {
+    instr.operands().first() == Some(&OperandKind::Relative)
+}
+
+#[inline]
+pub fn has_offset(mode: Instr) -> bool {
+    mode == Instr::type_ptr_off || mode == Instr::type_idr_ptr_off
+}
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DecodeErrorKind {
+    // byte is not an executable instruction
+    InvalidInstr(u8),
+    // byte is not a type_* prefix
+    InvalidType(u8),
+    // byte is not a size_* prefix
+    InvalidSize(u8),
+    // varint operand does not fit in 64 bits
+    InvalidVarint,
+    // program ends inside the instruction
+    Truncated,
+}
+
+impl fmt::Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeErrorKind::InvalidInstr(byte) => write!(f, "invalid instruction {}", byte),
+            DecodeErrorKind::InvalidType(byte) => write!(f, "invalid operand type {}", byte),
+            DecodeErrorKind::InvalidSize(byte) => write!(f, "invalid operand size {}", byte),
+            DecodeErrorKind::InvalidVarint => write!(f, "invalid varint operand"),
+            DecodeErrorKind::Truncated => write!(f, "unexpected end of program"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DecodeError {
+    kind: DecodeErrorKind,
+    offset: usize,
+}
+
+impl DecodeError {
+    #[inline]
+    pub fn new(kind: DecodeErrorKind, offset: usize) -> Self {
+        DecodeError {
+            kind: kind,
+            offset: offset,
+        }
+    }
+
+    #[inline]
+    pub fn get_kind(&self) -> DecodeErrorKind {self.kind}
+    // offset of the offending byte
+    #[inline]
+    pub fn get_offset(&self) -> usize {self.offset}
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.offset)
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Operand {
+    // type_* prefix
+    mode: Instr,
+    // immediate value or address, signed varint immediates sign extended
+    value: u64,
+    // second address of type_ptr_off and type_idr_ptr_off
+    offset: u64,
+}
+
+impl Operand {
+    #[inline]
+    pub fn get_mode(&self) -> Instr {self.mode}
+    #[inline]
+    pub fn get_value(&self) -> u64 {self.value}
+    #[inline]
+    pub fn get_offset(&self) -> u64 {self.offset}
+
+    // the value as the instruction reads it, truncated to value_type
+    #[inline]
+    pub fn get_typed_value(&self, value_type: ValueType) -> u64 {
+        match value_type.bits() {
+            64 => self.value,
+            bits => self.value & ((1 << bits) - 1),
+        }
+    }
+}
+
+// operands of a single instruction
+const MAX_OPERANDS: usize = 2;
+
+const NO_OPERAND: Operand = Operand {
+    mode: Instr::type_int,
+    value: 0,
+    offset: 0,
+};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Decoded {
+    instr: Instr,
+    operands: [Operand; MAX_OPERANDS],
+    operand_count: usize,
+    // offset of the opcode
+    offset: usize,
+    // encoded bytes including the operand
+    size: usize,
+}
+
+impl Decoded {
+    #[inline]
+    pub fn get_instr(&self) -> Instr {self.instr}
+    #[inline]
+    pub fn get_operand(&self) -> Option<Operand> {self.get_operands().first().cloned()}
+    // operands in encoding order, one per instr.operands()
+    #[inline]
+    pub fn get_operands(&self) -> &[Operand] {&self.operands[..self.operand_count]}
+    #[inline]
+    pub fn get_offset(&self) -> usize {self.offset}
+    #[inline]
+    pub fn get_size(&self) -> usize {self.size}
+    // offset of the next instruction
+    #[inline]
+    pub fn get_end(&self) -> usize {self.offset + self.size}
+
+    // jmp, if_jmp or call target given as an immediate, relative targets
+    // are resolved against the offset of the instruction
+    #[inline]
+    pub fn get_target(&self) -> Option<usize> {
+        match self.get_operand() {
+            Some(operand) if is_relative(self.instr) && operand.mode == Instr::type_int => {
+                let offset = operand.get_typed_value(ValueType::I32) as u32 as i32;
+                Some(self.offset.wrapping_add(offset as isize as usize))
+            },
+            Some(operand) if is_branch(self.instr) && operand.mode == Instr::type_int => {
+                Some(operand.value as usize)
+            },
+            _ => None,
+        }
+    }
+}
+
+
+struct Reader<'a> {
+    program: &'a [u8],
+    offset: usize,
+    encoding: Encoding,
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn next(&mut self) -> Result<u8, DecodeError> {
+        match self.program.get(self.offset) {
+            Some(byte) => {
+                self.offset += 1;
+                Ok(*byte)
+            },
+            None => Err(DecodeError::new(DecodeErrorKind::Truncated, self.offset)),
+        }
+    }
+
+    fn sized(&mut self) -> Result<u64, DecodeError> {
+        let offset = self.offset;
+        if self.encoding == Encoding::Varint {
+            return match encoding::read_varint(self.program, offset) {
+                Ok((value, end)) => {
+                    self.offset = end;
+                    Ok(value)
+                },
+                Err(VarintError::Truncated) => Err(DecodeError::new(DecodeErrorKind::Truncated, self.program.len())),
+                Err(VarintError::Overlong) => Err(DecodeError::new(DecodeErrorKind::InvalidVarint, offset)),
+            };
+        }
+        let size = self.next()?;
+
+        let bytes = match Instr::try_from(size) {
+            Ok(Instr::size_8) => 1,
+            Ok(Instr::size_16) => 2,
+            Ok(Instr::size_32) => 4,
+            Ok(Instr::size_64) => 8,
+            _ => return Err(DecodeError::new(DecodeErrorKind::InvalidSize(size), offset)),
+        };
+
+        let mut value = 0u64;
+        for _ in 0..bytes {
+            value = value << 8 | self.next()? as u64;
+        }
+        Ok(value)
+    }
+
+    // value_type is the type of a type_int immediate
+    fn operand(&mut self, value_type: ValueType) -> Result<Operand, DecodeError> {
+        let offset = self.offset;
+        let kind = self.next()?;
+
+        let mode = match Instr::try_from(kind) {
+            Ok(mode @ Instr::type_int) |
+            Ok(mode @ Instr::type_ptr) |
+            Ok(mode @ Instr::type_ptr_off) |
+            Ok(mode @ Instr::type_idr_ptr) |
+            Ok(mode @ Instr::type_idr_ptr_off) => mode,
+            _ => return Err(DecodeError::new(DecodeErrorKind::InvalidType(kind), offset)),
+        };
+        let mut value = self.sized()?;
+        if self.encoding == Encoding::Varint && mode == Instr::type_int && value_type.is_signed() {
+            value = encoding::unzigzag(value) as u64;
+        }
+        let offset = if has_offset(mode) {self.sized()?} else {0};
+
+        Ok(Operand {
+            mode: mode,
+            value: value,
+            offset: offset,
+        })
+    }
+}
+
+
+// decodes the instruction at offset without executing it, operands are
+// read in the encoding selected by the program header
+pub fn decode(program: &[u8], offset: usize) -> Result<Decoded, DecodeError> {
+    let mut reader = Reader {
+        program: program,
+        offset: offset,
+        encoding: encoding::read_header(program).0,
+    };
+    let opcode = reader.next()?;
+
+    let instr = match Instr::try_from(opcode) {
+        Ok(Instr::ext) => {
+            let opcode = reader.next()?;
+            match Instr::from_extended(opcode) {
+                Ok(instr) => instr,
+                Err(_) => return Err(DecodeError::new(DecodeErrorKind::InvalidInstr(opcode), offset + 1)),
+            }
+        },
+        Ok(instr) if !is_marker(instr) => instr,
+        _ => return Err(DecodeError::new(DecodeErrorKind::InvalidInstr(opcode), offset)),
+    };
+    let mut operands = [NO_OPERAND; MAX_OPERANDS];
+    let kinds = instr.operands();
+    for (index, kind) in kinds.iter().enumerate() {
+        operands[index] = reader.operand(kind_type(instr, *kind))?;
+    }
+
+    Ok(Decoded {
+        instr: instr,
+        operands: operands,
+        operand_count: kinds.len(),
+        offset: offset,
+        size: reader.offset - offset,
+    })
+}
@@ -0,0 +1,132 @@
+use core::fmt;
+
+use vector::Vector;
+use stack::Stack;
+
+use instrs::{Instr, ValueType};
+use decoder::{self, Operand, kind_type, has_offset};
+use encoding::{self, Encoding};
+
+
+#[inline]
+fn mode_keyword(mode: Instr) -> &'static str {
+    match mode {
+        Instr::type_ptr => "ptr",
+        Instr::type_ptr_off => "ptr_off",
+        Instr::type_idr_ptr => "idr_ptr",
+        Instr::type_idr_ptr_off => "idr_ptr_off",
+        _ => "int",
+    }
+}
+
+// writes an immediate the way the assembler parses it back, a NaN as its
+// bits since parsing NaN loses its sign and payload
+fn write_value<W: fmt::Write>(out: &mut W, bits: u64, value_type: ValueType) -> fmt::Result {
+    match value_type {
+        ValueType::U8 | ValueType::U16 | ValueType::U32 | ValueType::U64 => write!(out, "{}", bits),
+        ValueType::I8 => write!(out, "{}", bits as u8 as i8),
+        ValueType::I16 => write!(out, "{}", bits as u16 as i16),
+        ValueType::I32 => write!(out, "{}", bits as u32 as i32),
+        ValueType::I64 => write!(out, "{}", bits as i64),
+        ValueType::F32 if f32::from_bits(bits as u32).is_nan() => write!(out, "0x{:08x}", bits as u32),
+        ValueType::F64 if f64::from_bits(bits).is_nan() => write!(out, "0x{:016x}", bits),
+        ValueType::F32 => write!(out, "{}", f32::from_bits(bits as u32)),
+        ValueType::F64 => write!(out, "{}", f64::from_bits(bits)),
+    }
+}
+
+// target is the offset a branch operand resolves to
+fn write_operand<W: fmt::Write>(
+    out: &mut W, operand: Operand, value_type: ValueType, target: Option<usize>, labels: &[usize]
+) -> fmt::Result {
+    let mode = operand.get_mode();
+
+    if mode != Instr::type_int {
+        write!(out, " {} {}", mode_keyword(mode), operand.get_value())?;
+        if has_offset(mode) {
+            write!(out, ", {}", operand.get_offset())?;
+        }
+        return Ok(());
+    }
+
+    match target {
+        Some(target) if labels.binary_search(&target).is_ok() => write!(out, " label_{}", target),
+        _ => {
+            out.write_char(' ')?;
+            write_value(out, operand.get_typed_value(value_type), value_type)
+        },
+    }
+}
+
+
+// writes program as assembler source, one instruction per line followed
+// by its offset, jmp, if_jmp and call targets, relative ones resolved,
+// that land on an instruction get a label_{offset} label, undecodable
+// bytes are skipped one at a time and reported in a comment, a program
+// header becomes an .encoding directive
+pub fn disassemble<W: fmt::Write>(program: &[u8], out: &mut W) -> fmt::Result {
+    let (encoding, start) = encoding::read_header(program);
+    let mut offsets = Vector::new();
+    let mut targets = Vector::new();
+    let mut offset = start;
+
+    while offset < program.len() {
+        match decoder::decode(program, offset) {
+            Ok(decoded) => {
+                offsets.push(offset);
+                if let Some(target) = decoded.get_target() {
+                    targets.push(target);
+                }
+                offset = decoded.get_end();
+            },
+            Err(_) => offset += 1,
+        }
+    }
+
+    // only targets on an instruction boundary can be labeled
+    let mut labels = Vector::new();
+    for target in targets.iter() {
+        if offsets.binary_search(target).is_ok() {
+            labels.push(*target);
+        }
+    }
+    labels.sort_unstable();
+
+    if start > 0 {
+        writeln!(out, ".encoding {}", match encoding {
+            Encoding::Fixed => "fixed",
+            Encoding::Varint => "varint",
+        })?;
+    }
+
+    let mut offset = start;
+    while offset < program.len() {
+        let decoded = match decoder::decode(program, offset) {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                writeln!(out, "    // {}: {}", offset, error.get_kind())?;
+                offset += 1;
+                continue;
+            },
+        };
+
+        if labels.binary_search(&offset).is_ok() {
+            writeln!(out, "label_{}:", offset)?;
+        }
+
+        let instr = decoded.get_instr();
+        write!(out, "    {}", instr.mnemonic())?;
+        for (index, (operand, kind)) in decoded.get_operands().iter().zip(instr.operands()).enumerate() {
+            let target = if index == 0 {decoded.get_target()} else {None};
+
+            if index > 0 {
+                out.write_char(',')?;
+            }
+            write_operand(out, *operand, kind_type(instr, *kind), target, &labels)?;
+        }
+        writeln!(out, " // {}", offset)?;
+
+        offset = decoded.get_end();
+    }
+    Ok(())
+}
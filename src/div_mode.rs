@@ -0,0 +1,10 @@
+
+
+// behavior of integer div_* and rem_* on a zero divisor or signed MIN / -1
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DivMode {
+    // raise DivideByZero or Overflow traps
+    Trap,
+    // x / 0 and x % 0 are 0, MIN / -1 wraps to MIN
+    Wrap,
+}
@@ -0,0 +1,121 @@
+use vector::Vector;
+use stack::Stack;
+
+use instrs::ValueType;
+
+
+// operand payload encoding, selected by the program header
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Encoding {
+    // size_* byte followed by a big endian payload
+    Fixed,
+    // LEB128, signed immediates zigzag encoded first
+    Varint,
+}
+
+// a program may start with 0xff 'v' 'm' flags, 0xff is not a valid opcode
+// so a headerless program is never mistaken for one
+pub const HEADER_LEN: usize = 4;
+const MAGIC: [u8; 3] = [0xff, b'v', b'm'];
+const FLAG_VARINT: u8 = 1;
+
+// encoding of program and the offset its code starts at, programs without
+// a valid header are Fixed and start at 0
+#[inline]
+pub fn read_header(program: &[u8]) -> (Encoding, usize) {
+    if program.len() < HEADER_LEN || program[..3] != MAGIC {
+        return (Encoding::Fixed, 0);
+    }
+    match program[3] {
+        0 => (Encoding::Fixed, HEADER_LEN),
+        FLAG_VARINT => (Encoding::Varint, HEADER_LEN),
+        _ => (Encoding::Fixed, 0),
+    }
+}
+
+#[inline]
+pub fn emit_header(program: &mut Vector<u8>, encoding: Encoding) {
+    for byte in MAGIC.iter() {
+        program.push(*byte);
+    }
+    program.push(match encoding {
+        Encoding::Fixed => 0,
+        Encoding::Varint => FLAG_VARINT,
+    });
+}
+
+
+#[inline]
+pub fn zigzag(value: i64) -> u64 {((value << 1) ^ (value >> 63)) as u64}
+#[inline]
+pub fn unzigzag(value: u64) -> i64 {((value >> 1) as i64) ^ -((value & 1) as i64)}
+
+// sign extends the low bits of a value_type immediate
+#[inline]
+pub fn sign_extend(bits: u64, value_type: ValueType) -> i64 {
+    let shift = 64 - value_type.bits();
+    ((bits << shift) as i64) >> shift
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VarintError {
+    // program ends inside the varint
+    Truncated,
+    // more than 64 bits
+    Overlong,
+}
+
+// reads the LEB128 varint at offset, returns it and the offset after it
+pub fn read_varint(program: &[u8], offset: usize) -> Result<(u64, usize), VarintError> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    let mut index = offset;
+
+    loop {
+        let byte = match program.get(index) {
+            Some(byte) => *byte,
+            None => return Err(VarintError::Truncated),
+        };
+        index += 1;
+
+        let bits = (byte & 0x7f) as u64;
+        if shift == 63 && bits > 1 || shift > 63 {
+            return Err(VarintError::Overlong);
+        }
+        value |= bits << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Ok((value, index));
+        }
+    }
+}
+
+#[inline]
+pub fn varint_len(value: u64) -> usize {
+    let mut len = 1;
+    let mut rest = value >> 7;
+    while rest != 0 {
+        len += 1;
+        rest >>= 7;
+    }
+    len
+}
+
+// writes value as a LEB128 varint padded with continuation bytes to at least len bytes
+pub fn emit_varint(program: &mut Vector<u8>, value: u64, len: usize) {
+    let mut rest = value;
+    let mut written = 0;
+
+    loop {
+        let byte = (rest & 0x7f) as u8;
+        rest >>= 7;
+        written += 1;
+
+        if rest == 0 && written >= len {
+            program.push(byte);
+            return;
+        }
+        program.push(byte | 0x80);
+    }
+}
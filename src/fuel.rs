@@ -0,0 +1,59 @@
+use instrs::{Instr, OperandKind, INSTR_COUNT};
+
+
+// fuel charged for every instruction before it is evaluated
+//
+// let mut costs = CostTable::new();
+// costs.set_cost(Instr::div_u64, 20).set_byte_cost(2);
+// process.set_cost_table(costs);
+// process.refuel(10_000);
+#[derive(Debug, Clone)]
+pub struct CostTable {
+    // indexed by the discriminant
+    costs: [u64; INSTR_COUNT],
+    // charged for every byte of the value push_*, load_* and save_* move
+    byte_cost: u64,
+}
+
+impl Default for CostTable {
+    #[inline]
+    fn default() -> Self {Self::new()}
+}
+
+impl CostTable {
+    // every instruction costs 1, plus 1 for every byte a push, load or save moves
+    #[inline]
+    pub fn new() -> Self {
+        CostTable {
+            costs: [1; INSTR_COUNT],
+            byte_cost: 1,
+        }
+    }
+
+    #[inline]
+    pub fn get_cost(&self, instr: Instr) -> u64 {self.costs[instr as usize]}
+    #[inline]
+    pub fn set_cost(&mut self, instr: Instr, cost: u64) -> &mut Self {
+        self.costs[instr as usize] = cost;
+        self
+    }
+
+    #[inline]
+    pub fn get_byte_cost(&self) -> u64 {self.byte_cost}
+    #[inline]
+    pub fn set_byte_cost(&mut self, byte_cost: u64) -> &mut Self {
+        self.byte_cost = byte_cost;
+        self
+    }
+
+    // cost of instr with the bytes it moves, saturates instead of overflowing
+    #[inline]
+    pub fn instr_cost(&self, instr: Instr) -> u64 {
+        let moves = instr.accesses_memory() || instr.operands() == &[OperandKind::Immediate][..];
+        let bytes = match instr.value_type() {
+            Some(value_type) if moves => value_type.bytes() as u64,
+            _ => 0,
+        };
+        self.get_cost(instr).saturating_add(self.byte_cost.saturating_mul(bytes))
+    }
+}
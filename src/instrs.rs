@@ -1,99 +1,501 @@
+use core::convert::TryFrom;
+use core::fmt;
 
+use self::ValueType::{U8, U16, U32, U64, I8, I16, I32, I64, F32, F64};
 
-#[allow(non_camel_case_types)]
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ValueType {
+    U8, U16, U32, U64,
+    I8, I16, I32, I64,
+    F32, F64,
+}
+
+impl ValueType {
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        match *self {
+            ValueType::U8 | ValueType::I8 => 8,
+            ValueType::U16 | ValueType::I16 => 16,
+            ValueType::U32 | ValueType::I32 | ValueType::F32 => 32,
+            ValueType::U64 | ValueType::I64 | ValueType::F64 => 64,
+        }
+    }
+    #[inline]
+    pub fn bytes(&self) -> usize {self.bits() as usize / 8}
+    #[inline]
+    pub fn is_integer(&self) -> bool {
+        *self != ValueType::F32 && *self != ValueType::F64
+    }
+    #[inline]
+    pub fn is_signed(&self) -> bool {
+        matches!(*self, ValueType::I8 | ValueType::I16 | ValueType::I32 | ValueType::I64)
+    }
+
+    // the _u8.._f64 suffix of a mnemonic
+    #[inline]
+    pub fn from_suffix(suffix: &str) -> Option<ValueType> {
+        match suffix {
+            "u8" => Some(ValueType::U8),
+            "u16" => Some(ValueType::U16),
+            "u32" => Some(ValueType::U32),
+            "u64" => Some(ValueType::U64),
+            "i8" => Some(ValueType::I8),
+            "i16" => Some(ValueType::I16),
+            "i32" => Some(ValueType::I32),
+            "i64" => Some(ValueType::I64),
+            "f32" => Some(ValueType::F32),
+            "f64" => Some(ValueType::F64),
+            _ => None,
+        }
+    }
+    #[inline]
+    pub fn suffix(&self) -> &'static str {
+        match *self {
+            ValueType::U8 => "u8",
+            ValueType::U16 => "u16",
+            ValueType::U32 => "u32",
+            ValueType::U64 => "u64",
+            ValueType::I8 => "i8",
+            ValueType::I16 => "i16",
+            ValueType::I32 => "i32",
+            ValueType::I64 => "i64",
+            ValueType::F32 => "f32",
+            ValueType::F64 => "f64",
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.suffix())
+    }
+}
+
+
+
+// operand read from the program after the opcode
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OperandKind {
+    // value of the instruction's type
+    Immediate,
+    // program offset to continue at
+    Target,
+    // i32 offset of the target from the start of the instruction
+    Relative,
+    // byte count or stack offset, read as usize
+    Count,
+}
+
+// bytes taken from and left on the stack
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub enum Instr {
+pub struct StackEffect {
+    pub popped: usize,
+    pub pushed: usize,
+}
+
+
+// metadata of an opcode, pop lists values top first, push bottom first
+struct Info {
+    operands: &'static [OperandKind],
+    value_type: Option<ValueType>,
+    pop: [ValueType; 3],
+    pops: usize,
+    push: [ValueType; 3],
+    pushes: usize,
+    // accepts the checked and sat prefixes
+    prefixable: bool,
+    // reads or writes process memory
+    memory: bool,
+}
 
-    nop = 0, halt,
+const fn info(
+    operands: &'static [OperandKind], value_type: Option<ValueType>,
+    pop: [ValueType; 3], pops: usize, push: [ValueType; 3], pushes: usize
+) -> Info {
+    Info {
+        operands: operands,
+        value_type: value_type,
+        pop: pop,
+        pops: pops,
+        push: push,
+        pushes: pushes,
+        prefixable: false,
+        memory: false,
+    }
+}
+
+const fn plain() -> Info {info(&[], None, [U8, U8, U8], 0, [U8, U8, U8], 0)}
+const fn jump() -> Info {info(&[OperandKind::Target], None, [U8, U8, U8], 0, [U8, U8, U8], 0)}
+const fn cond_jump() -> Info {info(&[OperandKind::Target], None, [U8, U8, U8], 1, [U8, U8, U8], 0)}
+const fn rel_jump() -> Info {info(&[OperandKind::Relative], None, [U8, U8, U8], 0, [U8, U8, U8], 0)}
+const fn rel_cond_jump() -> Info {info(&[OperandKind::Relative], None, [U8, U8, U8], 1, [U8, U8, U8], 0)}
+const fn exit() -> Info {info(&[], None, [U32, U32, U32], 1, [U8, U8, U8], 0)}
+const fn event() -> Info {info(&[], None, [U64, U64, U64], 1, [U8, U8, U8], 0)}
+// target is a usize popped from the stack
+const fn indirect_jump() -> Info {info(&[], None, [U64, U64, U64], 1, [U8, U8, U8], 0)}
+// the argument bytes move into the callee's frame
+const fn frame_call() -> Info {info(&[OperandKind::Target, OperandKind::Count], None, [U8, U8, U8], 0, [U8, U8, U8], 0)}
+// reserves its operand's bytes of locals, which metadata cannot express
+const fn frame_enter() -> Info {info(&[OperandKind::Count], None, [U8, U8, U8], 0, [U8, U8, U8], 0)}
+const fn local_get(t: ValueType) -> Info {info(&[OperandKind::Count], Some(t), [t, t, t], 0, [t, t, t], 1)}
+const fn local_set(t: ValueType) -> Info {info(&[OperandKind::Count], Some(t), [t, t, t], 1, [t, t, t], 0)}
+const fn swap(t: ValueType) -> Info {info(&[], Some(t), [t, t, t], 2, [t, t, t], 2)}
+const fn over(t: ValueType) -> Info {info(&[], Some(t), [t, t, t], 2, [t, t, t], 3)}
+const fn rot(t: ValueType) -> Info {info(&[], Some(t), [t, t, t], 3, [t, t, t], 3)}
+// how deep pick and roll reach and how much drop_bytes pops depends on
+// the operand, which metadata cannot express
+const fn pick(t: ValueType) -> Info {info(&[OperandKind::Count], Some(t), [t, t, t], 0, [t, t, t], 1)}
+const fn roll(t: ValueType) -> Info {info(&[OperandKind::Count], Some(t), [t, t, t], 0, [t, t, t], 0)}
+// compare and if_jmp in one, pop values and jump to target when the
+// comparison holds
+const fn branch(t: ValueType) -> Info {info(&[OperandKind::Target], Some(t), [t, t, t], 2, [t, t, t], 0)}
+const fn zero_branch(t: ValueType) -> Info {info(&[OperandKind::Target], Some(t), [t, t, t], 1, [t, t, t], 0)}
+const fn drop_bytes() -> Info {info(&[OperandKind::Count], None, [U8, U8, U8], 0, [U8, U8, U8], 0)}
+// the argument bytes move onto the child's stack, which metadata cannot express
+const fn spawn() -> Info {info(&[OperandKind::Target, OperandKind::Count], None, [U8, U8, U8], 0, [U64, U64, U64], 1)}
+
+const fn push(t: ValueType) -> Info {info(&[OperandKind::Immediate], Some(t), [t, t, t], 0, [t, t, t], 1)}
+const fn pop(t: ValueType) -> Info {info(&[], Some(t), [t, t, t], 1, [t, t, t], 0)}
+const fn copy(t: ValueType) -> Info {info(&[], Some(t), [t, t, t], 1, [t, t, t], 2)}
+// addresses are usize
+const fn load(t: ValueType) -> Info {Info {memory: true, ..info(&[], Some(t), [U64, U64, U64], 1, [t, t, t], 1)}}
+const fn save(t: ValueType) -> Info {Info {memory: true, ..info(&[], Some(t), [U64, t, t], 2, [t, t, t], 0)}}
+
+const fn binary(t: ValueType) -> Info {info(&[], Some(t), [t, t, t], 2, [t, t, t], 1)}
+const fn unary(t: ValueType) -> Info {info(&[], Some(t), [t, t, t], 1, [t, t, t], 1)}
+const fn compare(t: ValueType) -> Info {info(&[], Some(t), [t, t, t], 2, [U8, U8, U8], 1)}
+const fn arith(t: ValueType) -> Info {Info {prefixable: true, ..binary(t)}}
+const fn negate(t: ValueType) -> Info {Info {prefixable: true, ..unary(t)}}
+// named is the type of the mnemonic's suffix
+const fn cvt(from: ValueType, to: ValueType, named: ValueType) -> Info {
+    info(&[], Some(named), [from, from, from], 1, [to, to, to], 1)
+}
+
+
+macro_rules! instrs {
+    ($($name: ident = $info: expr,)*) => {
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+        #[repr(u16)]
+        pub enum Instr {
+            $($name,)*
+        }
+
+        // opcode table, indexed by the discriminant
+        static INSTRS: &'static [Instr] = &[$(Instr::$name,)*];
+        // mnemonic table, indexed by the discriminant
+        static MNEMONICS: &'static [&'static str] = &[$(stringify!($name),)*];
+        // metadata table, indexed by the discriminant
+        static INFOS: &'static [Info] = &[$($info,)*];
+
+        // number of instructions, extended ones included
+        pub const INSTR_COUNT: usize = [$(Instr::$name,)*].len();
+    };
+}
+
+
+instrs! {
+
+    nop = plain(), halt = plain(),
 
     // branching
     // if top of stack value is non-zero jump to location
-    if_jmp,
+    if_jmp = cond_jump(),
     // (location: usize)
-    jmp,
+    jmp = jump(),
 
     // function call, return
-    call, ret,
+    call = jump(), ret = plain(),
 
     // types
     // instructs the vm to interpret the next bytes as
     // pointer/indirect, pointer/indirect offset or intermediate value
-    type_int, type_ptr, type_ptr_off, type_idr_ptr, type_idr_ptr_off,
+    type_int = plain(), type_ptr = plain(), type_ptr_off = plain(), type_idr_ptr = plain(), type_idr_ptr_off = plain(),
     // sizes
-    size_8, size_16, size_32, size_64,
+    size_8 = plain(), size_16 = plain(), size_32 = plain(), size_64 = plain(),
 
     // stack
-    push_u8, push_u16, push_u32, push_u64,
-    push_i8, push_i16, push_i32, push_i64, push_f32, push_f64,
+    push_u8 = push(U8), push_u16 = push(U16), push_u32 = push(U32), push_u64 = push(U64),
+    push_i8 = push(I8), push_i16 = push(I16), push_i32 = push(I32), push_i64 = push(I64), push_f32 = push(F32), push_f64 = push(F64),
 
-    pop_u8, pop_u16, pop_u32, pop_u64,
-    pop_i8, pop_i16, pop_i32, pop_i64, pop_f32, pop_f64,
+    pop_u8 = pop(U8), pop_u16 = pop(U16), pop_u32 = pop(U32), pop_u64 = pop(U64),
+    pop_i8 = pop(I8), pop_i16 = pop(I16), pop_i32 = pop(I32), pop_i64 = pop(I64), pop_f32 = pop(F32), pop_f64 = pop(F64),
 
-    copy_u8, copy_u16, copy_u32, copy_u64,
-    copy_i8, copy_i16, copy_i32, copy_i64, copy_f32, copy_f64,
+    copy_u8 = copy(U8), copy_u16 = copy(U16), copy_u32 = copy(U32), copy_u64 = copy(U64),
+    copy_i8 = copy(I8), copy_i16 = copy(I16), copy_i32 = copy(I32), copy_i64 = copy(I64), copy_f32 = copy(F32), copy_f64 = copy(F64),
 
     // memory
-    load_u8, load_u16, load_u32, load_u64,
-    load_i8, load_i16, load_i32, load_i64, load_f32, load_f64,
+    load_u8 = load(U8), load_u16 = load(U16), load_u32 = load(U32), load_u64 = load(U64),
+    load_i8 = load(I8), load_i16 = load(I16), load_i32 = load(I32), load_i64 = load(I64), load_f32 = load(F32), load_f64 = load(F64),
 
-    save_u8, save_u16, save_u32, save_u64,
-    save_i8, save_i16, save_i32, save_i64, save_f32, save_f64,
+    save_u8 = save(U8), save_u16 = save(U16), save_u32 = save(U32), save_u64 = save(U64),
+    save_i8 = save(I8), save_i16 = save(I16), save_i32 = save(I32), save_i64 = save(I64), save_f32 = save(F32), save_f64 = save(F64),
 
     //binary operations (a: ptr, b: ptr, out: ptr)
-    add_u8, add_u16, add_u32, add_u64,
-    add_i8, add_i16, add_i32, add_i64, add_f32, add_f64,
+    add_u8 = arith(U8), add_u16 = arith(U16), add_u32 = arith(U32), add_u64 = arith(U64),
+    add_i8 = arith(I8), add_i16 = arith(I16), add_i32 = arith(I32), add_i64 = arith(I64), add_f32 = binary(F32), add_f64 = binary(F64),
 
-    sub_u8, sub_u16, sub_u32, sub_u64,
-    sub_i8, sub_i16, sub_i32, sub_i64, sub_f32, sub_f64,
+    sub_u8 = arith(U8), sub_u16 = arith(U16), sub_u32 = arith(U32), sub_u64 = arith(U64),
+    sub_i8 = arith(I8), sub_i16 = arith(I16), sub_i32 = arith(I32), sub_i64 = arith(I64), sub_f32 = binary(F32), sub_f64 = binary(F64),
 
-    mul_u8, mul_u16, mul_u32, mul_u64,
-    mul_i8, mul_i16, mul_i32, mul_i64, mul_f32, mul_f64,
+    mul_u8 = arith(U8), mul_u16 = arith(U16), mul_u32 = arith(U32), mul_u64 = arith(U64),
+    mul_i8 = arith(I8), mul_i16 = arith(I16), mul_i32 = arith(I32), mul_i64 = arith(I64), mul_f32 = binary(F32), mul_f64 = binary(F64),
 
-    div_u8, div_u16, div_u32, div_u64,
-    div_i8, div_i16, div_i32, div_i64, div_f32, div_f64,
+    div_u8 = binary(U8), div_u16 = binary(U16), div_u32 = binary(U32), div_u64 = binary(U64),
+    div_i8 = binary(I8), div_i16 = binary(I16), div_i32 = binary(I32), div_i64 = binary(I64), div_f32 = binary(F32), div_f64 = binary(F64),
 
-    rem_u8, rem_u16, rem_u32, rem_u64,
-    rem_i8, rem_i16, rem_i32, rem_i64, rem_f32, rem_f64,
+    rem_u8 = binary(U8), rem_u16 = binary(U16), rem_u32 = binary(U32), rem_u64 = binary(U64),
+    rem_i8 = binary(I8), rem_i16 = binary(I16), rem_i32 = binary(I32), rem_i64 = binary(I64), rem_f32 = binary(F32), rem_f64 = binary(F64),
 
     // bitwise operations (a: ptr, b: ptr, out: ptr)
-    and_u8, and_u16, and_u32, and_u64,
-    and_i8, and_i16, and_i32, and_i64,
+    and_u8 = binary(U8), and_u16 = binary(U16), and_u32 = binary(U32), and_u64 = binary(U64),
+    and_i8 = binary(I8), and_i16 = binary(I16), and_i32 = binary(I32), and_i64 = binary(I64),
 
-    or_u8, or_u16, or_u32, or_u64,
-    or_i8, or_i16, or_i32, or_i64,
+    or_u8 = binary(U8), or_u16 = binary(U16), or_u32 = binary(U32), or_u64 = binary(U64),
+    or_i8 = binary(I8), or_i16 = binary(I16), or_i32 = binary(I32), or_i64 = binary(I64),
 
-    xor_u8, xor_u16, xor_u32, xor_u64,
-    xor_i8, xor_i16, xor_i32, xor_i64,
+    xor_u8 = binary(U8), xor_u16 = binary(U16), xor_u32 = binary(U32), xor_u64 = binary(U64),
+    xor_i8 = binary(I8), xor_i16 = binary(I16), xor_i32 = binary(I32), xor_i64 = binary(I64),
 
-    shl_u8, shl_u16, shl_u32, shl_u64,
-    shl_i8, shl_i16, shl_i32, shl_i64,
+    shl_u8 = binary(U8), shl_u16 = binary(U16), shl_u32 = binary(U32), shl_u64 = binary(U64),
+    shl_i8 = binary(I8), shl_i16 = binary(I16), shl_i32 = binary(I32), shl_i64 = binary(I64),
 
-    shr_u8, shr_u16, shr_u32, shr_u64,
-    shr_i8, shr_i16, shr_i32, shr_i64,
+    shr_u8 = binary(U8), shr_u16 = binary(U16), shr_u32 = binary(U32), shr_u64 = binary(U64),
+    shr_i8 = binary(I8), shr_i16 = binary(I16), shr_i32 = binary(I32), shr_i64 = binary(I64),
 
     // single operators (value: ptr, out: ptr)
-    not_u8, not_u16, not_u32, not_u64,
-    not_i8, not_i16, not_i32, not_i64,
+    not_u8 = unary(U8), not_u16 = unary(U16), not_u32 = unary(U32), not_u64 = unary(U64),
+    not_i8 = unary(I8), not_i16 = unary(I16), not_i32 = unary(I32), not_i64 = unary(I64),
 
-    neg_u8, neg_u16, neg_u32, neg_u64,
-    neg_i8, neg_i16, neg_i32, neg_i64, neg_f32, neg_f64,
+    neg_u8 = negate(U8), neg_u16 = negate(U16), neg_u32 = negate(U32), neg_u64 = negate(U64),
+    neg_i8 = negate(I8), neg_i16 = negate(I16), neg_i32 = negate(I32), neg_i64 = negate(I64), neg_f32 = unary(F32), neg_f64 = unary(F64),
 
     // comparison operators (a: ptr, b: ptr, out: ptr)
-    lt_u8, lt_u16, lt_u32, lt_u64,
-    lt_i8, lt_i16, lt_i32, lt_i64, lt_f32, lt_f64,
+    lt_u8 = compare(U8), lt_u16 = compare(U16), lt_u32 = compare(U32), lt_u64 = compare(U64),
+    lt_i8 = compare(I8), lt_i16 = compare(I16), lt_i32 = compare(I32), lt_i64 = compare(I64), lt_f32 = compare(F32), lt_f64 = compare(F64),
+
+    lte_u8 = compare(U8), lte_u16 = compare(U16), lte_u32 = compare(U32), lte_u64 = compare(U64),
+    lte_i8 = compare(I8), lte_i16 = compare(I16), lte_i32 = compare(I32), lte_i64 = compare(I64), lte_f32 = compare(F32), lte_f64 = compare(F64),
+
+    gt_u8 = compare(U8), gt_u16 = compare(U16), gt_u32 = compare(U32), gt_u64 = compare(U64),
+    gt_i8 = compare(I8), gt_i16 = compare(I16), gt_i32 = compare(I32), gt_i64 = compare(I64), gt_f32 = compare(F32), gt_f64 = compare(F64),
+
+    gte_u8 = compare(U8), gte_u16 = compare(U16), gte_u32 = compare(U32), gte_u64 = compare(U64),
+    gte_i8 = compare(I8), gte_i16 = compare(I16), gte_i32 = compare(I32), gte_i64 = compare(I64), gte_f32 = compare(F32), gte_f64 = compare(F64),
+
+    eq_u8 = compare(U8), eq_u16 = compare(U16), eq_u32 = compare(U32), eq_u64 = compare(U64),
+    eq_i8 = compare(I8), eq_i16 = compare(I16), eq_i32 = compare(I32), eq_i64 = compare(I64), eq_f32 = compare(F32), eq_f64 = compare(F64),
+
+    neq_u8 = compare(U8), neq_u16 = compare(U16), neq_u32 = compare(U32), neq_u64 = compare(U64),
+    neq_i8 = compare(I8), neq_i16 = compare(I16), neq_i32 = compare(I32), neq_i64 = compare(I64), neq_f32 = compare(F32), neq_f64 = compare(F64),
+
+    // conversions, every conversion goes through the 64 bit types
+    // zero extend unsigned and sign extend signed values to 64 bits
+    ext_u8 = cvt(U8, U64, U8), ext_u16 = cvt(U16, U64, U16), ext_u32 = cvt(U32, U64, U32),
+    ext_i8 = cvt(I8, I64, I8), ext_i16 = cvt(I16, I64, I16), ext_i32 = cvt(I32, I64, I32),
+    // truncate a 64 bit value to its low bits
+    wrap_u8 = cvt(U64, U8, U8), wrap_u16 = cvt(U64, U16, U16), wrap_u32 = cvt(U64, U32, U32),
+    // integer to float, rounds to nearest
+    cvt_i64_f32 = cvt(I64, F32, F32), cvt_u64_f32 = cvt(U64, F32, F32), cvt_i64_f64 = cvt(I64, F64, F64), cvt_u64_f64 = cvt(U64, F64, F64),
+    // float to integer, truncates toward zero, saturates when out of range, NaN is 0
+    cvt_f32_i64 = cvt(F32, I64, I64), cvt_f32_u64 = cvt(F32, U64, U64), cvt_f64_i64 = cvt(F64, I64, I64), cvt_f64_u64 = cvt(F64, U64, U64),
+    promote_f32 = cvt(F32, F64, F32), demote_f64 = cvt(F64, F32, F64),
+
+    // overflow prefixes, integer add, sub, mul and neg wrap on overflow,
+    // when prefixed by checked they trap, when prefixed by sat they saturate
+    checked = plain(), sat = plain(),
+
+    // escape, the next byte selects one of the extended instructions below,
+    // which are encoded as two bytes and cannot be cast to a single u8
+    ext = plain(),
+
+    // float math
+    abs_f32 = unary(F32), abs_f64 = unary(F64),
+    min_f32 = binary(F32), min_f64 = binary(F64),
+    max_f32 = binary(F32), max_f64 = binary(F64),
+
+    // relative branching, position independent jmp, if_jmp and call
+    // (offset: i32)
+    jmp_rel = rel_jump(), if_jmp_rel = rel_cond_jump(), call_rel = rel_jump(),
+
+    // computed branching, pop the target (location: usize)
+    jmp_indirect = indirect_jump(), call_indirect = indirect_jump(),
+
+    // call frames, call_args moves the top (args: usize) bytes into the
+    // callee's frame, enter reserves (locals: usize) zeroed bytes after them,
+    // ret leaves only what was pushed after the locals
+    // (location: usize, args: usize)
+    call_args = frame_call(), enter = frame_enter(),
+
+    // frame access (offset: usize), from the frame pointer, the first argument
+    local_get_u8 = local_get(U8), local_get_u16 = local_get(U16), local_get_u32 = local_get(U32), local_get_u64 = local_get(U64),
+    local_get_i8 = local_get(I8), local_get_i16 = local_get(I16), local_get_i32 = local_get(I32), local_get_i64 = local_get(I64),
+    local_get_f32 = local_get(F32), local_get_f64 = local_get(F64),
+
+    local_set_u8 = local_set(U8), local_set_u16 = local_set(U16), local_set_u32 = local_set(U32), local_set_u64 = local_set(U64),
+    local_set_i8 = local_set(I8), local_set_i16 = local_set(I16), local_set_i32 = local_set(I32), local_set_i64 = local_set(I64),
+    local_set_f32 = local_set(F32), local_set_f64 = local_set(F64),
+
+    // stack shuffling, swap exchanges the top two values, over copies the
+    // second to the top, rot moves the third to the top
+    swap_u8 = swap(U8), swap_u16 = swap(U16), swap_u32 = swap(U32), swap_u64 = swap(U64),
+    swap_i8 = swap(I8), swap_i16 = swap(I16), swap_i32 = swap(I32), swap_i64 = swap(I64),
+    swap_f32 = swap(F32), swap_f64 = swap(F64),
+
+    over_u8 = over(U8), over_u16 = over(U16), over_u32 = over(U32), over_u64 = over(U64),
+    over_i8 = over(I8), over_i16 = over(I16), over_i32 = over(I32), over_i64 = over(I64),
+    over_f32 = over(F32), over_f64 = over(F64),
+
+    rot_u8 = rot(U8), rot_u16 = rot(U16), rot_u32 = rot(U32), rot_u64 = rot(U64),
+    rot_i8 = rot(I8), rot_i16 = rot(I16), rot_i32 = rot(I32), rot_i64 = rot(I64),
+    rot_f32 = rot(F32), rot_f64 = rot(F64),
+
+    // pick copies and roll moves the value (depth: usize) values below the
+    // top to the top, pick 0 is copy, roll 1 is swap, roll 2 is rot
+    pick_u8 = pick(U8), pick_u16 = pick(U16), pick_u32 = pick(U32), pick_u64 = pick(U64),
+    pick_i8 = pick(I8), pick_i16 = pick(I16), pick_i32 = pick(I32), pick_i64 = pick(I64),
+    pick_f32 = pick(F32), pick_f64 = pick(F64),
+
+    roll_u8 = roll(U8), roll_u16 = roll(U16), roll_u32 = roll(U32), roll_u64 = roll(U64),
+    roll_i8 = roll(I8), roll_i16 = roll(I16), roll_i32 = roll(I32), roll_i64 = roll(I64),
+    roll_f32 = roll(F32), roll_f64 = roll(F64),
+
+    // pops (count: usize) bytes
+    drop_bytes = drop_bytes(),
+
+    // fused compare and branch (location: usize), br_eq and br_lt pop two
+    // values like eq and lt, br_zero and br_nonzero pop one
+    br_eq_u8 = branch(U8), br_eq_u16 = branch(U16), br_eq_u32 = branch(U32), br_eq_u64 = branch(U64),
+    br_eq_i8 = branch(I8), br_eq_i16 = branch(I16), br_eq_i32 = branch(I32), br_eq_i64 = branch(I64),
+    br_eq_f32 = branch(F32), br_eq_f64 = branch(F64),
+
+    br_lt_u8 = branch(U8), br_lt_u16 = branch(U16), br_lt_u32 = branch(U32), br_lt_u64 = branch(U64),
+    br_lt_i8 = branch(I8), br_lt_i16 = branch(I16), br_lt_i32 = branch(I32), br_lt_i64 = branch(I64),
+    br_lt_f32 = branch(F32), br_lt_f64 = branch(F64),
+
+    br_zero_u8 = zero_branch(U8), br_zero_u16 = zero_branch(U16), br_zero_u32 = zero_branch(U32), br_zero_u64 = zero_branch(U64),
+    br_zero_i8 = zero_branch(I8), br_zero_i16 = zero_branch(I16), br_zero_i32 = zero_branch(I32), br_zero_i64 = zero_branch(I64),
+    br_zero_f32 = zero_branch(F32), br_zero_f64 = zero_branch(F64),
+
+    br_nonzero_u8 = zero_branch(U8), br_nonzero_u16 = zero_branch(U16), br_nonzero_u32 = zero_branch(U32), br_nonzero_u64 = zero_branch(U64),
+    br_nonzero_i8 = zero_branch(I8), br_nonzero_i16 = zero_branch(I16), br_nonzero_i32 = zero_branch(I32), br_nonzero_i64 = zero_branch(I64),
+    br_nonzero_f32 = zero_branch(F32), br_nonzero_f64 = zero_branch(F64),
+
+    // stops vm::run with the popped exit code (code: u32)
+    halt_with = exit(),
+
+    // pause yields to the host, wait pops the (event: u64) it blocks on
+    // until the process is woken
+    pause = plain(), wait = event(),
+
+    // starts a process at (location: usize) sharing the program, moves the
+    // top (args: usize) bytes onto its stack and pushes its (pid: u64)
+    spawn = spawn(),
+}
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct InvalidOpcode(pub u8);
+
+impl fmt::Display for InvalidOpcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid opcode {}", self.0)
+    }
+}
+
+
+impl TryFrom<u8> for Instr {
+    type Error = InvalidOpcode;
+
+    // single byte opcodes, ext included
+    #[inline]
+    fn try_from(opcode: u8) -> Result<Self, Self::Error> {
+        if (opcode as usize) < Instr::count() {
+            Ok(INSTRS[opcode as usize])
+        } else {
+            Err(InvalidOpcode(opcode))
+        }
+    }
+}
+
+impl Instr {
+    // number of single byte opcodes, ext included
+    #[inline]
+    pub fn count() -> usize {Instr::ext as usize + 1}
+    // number of extended opcodes
+    #[inline]
+    pub fn extended_count() -> usize {INSTRS.len() - Instr::count()}
+
+    // extended instruction selected by the byte following ext
+    #[inline]
+    pub fn from_extended(opcode: u8) -> Result<Instr, InvalidOpcode> {
+        match INSTRS.get(Instr::count() + opcode as usize) {
+            Some(instr) => Ok(*instr),
+            None => Err(InvalidOpcode(opcode)),
+        }
+    }
+    #[inline]
+    pub fn is_extended(&self) -> bool {*self as usize >= Instr::count()}
+
+    // first encoded byte, ext for extended instructions
+    #[inline]
+    pub fn opcode(&self) -> u8 {
+        if self.is_extended() {Instr::ext as u8} else {*self as u8}
+    }
+    // byte following ext
+    #[inline]
+    pub fn extended_opcode(&self) -> Option<u8> {
+        if self.is_extended() {Some((*self as usize - Instr::count()) as u8)} else {None}
+    }
+    // encoded bytes of the opcode, without operand
+    #[inline]
+    pub fn opcode_len(&self) -> usize {
+        if self.is_extended() {2} else {1}
+    }
 
-    lte_u8, lte_u16, lte_u32, lte_u64,
-    lte_i8, lte_i16, lte_i32, lte_i64, lte_f32, lte_f64,
+    #[inline]
+    pub fn mnemonic(&self) -> &'static str {MNEMONICS[*self as usize]}
+    #[inline]
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Instr> {
+        MNEMONICS.iter().position(|name| *name == mnemonic).map(|index| INSTRS[index])
+    }
 
-    gt_u8, gt_u16, gt_u32, gt_u64,
-    gt_i8, gt_i16, gt_i32, gt_i64, gt_f32, gt_f64,
+    #[inline]
+    fn info(&self) -> &'static Info {&INFOS[*self as usize]}
 
-    gte_u8, gte_u16, gte_u32, gte_u64,
-    gte_i8, gte_i16, gte_i32, gte_i64, gte_f32, gte_f64,
+    // operands read after the opcode, empty if it takes none
+    #[inline]
+    pub fn operands(&self) -> &'static [OperandKind] {self.info().operands}
+    // type named by the _u8.._f64 suffix of the mnemonic
+    #[inline]
+    pub fn value_type(&self) -> Option<ValueType> {self.info().value_type}
 
-    eq_u8, eq_u16, eq_u32, eq_u64,
-    eq_i8, eq_i16, eq_i32, eq_i64, eq_f32, eq_f64,
+    // values popped, top first, ret, call and the jumps leave the stack
+    // alone besides the condition of if_jmp
+    #[inline]
+    pub fn pops(&self) -> &'static [ValueType] {
+        let info = self.info();
+        &info.pop[..info.pops]
+    }
+    // values pushed, bottom first
+    #[inline]
+    pub fn pushes(&self) -> &'static [ValueType] {
+        let info = self.info();
+        &info.push[..info.pushes]
+    }
+    #[inline]
+    pub fn stack_effect(&self) -> StackEffect {
+        StackEffect {
+            popped: self.pops().iter().map(|value_type| value_type.bytes()).sum(),
+            pushed: self.pushes().iter().map(|value_type| value_type.bytes()).sum(),
+        }
+    }
 
-    neq_u8, neq_u16, neq_u32, neq_u64,
-    neq_i8, neq_i16, neq_i32, neq_i64, neq_f32, neq_f64,
+    // accepts the checked and sat prefixes
+    #[inline]
+    pub fn is_prefixable(&self) -> bool {self.info().prefixable}
+    // load_* and save_*
+    #[inline]
+    pub fn accesses_memory(&self) -> bool {self.info().memory}
 }
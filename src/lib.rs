@@ -7,13 +7,39 @@ extern crate stack;
 extern crate remove;
 
 
+mod analysis;
+mod assembler;
+mod builder;
+mod decoder;
+mod disassembler;
+mod div_mode;
+mod encoding;
+mod fuel;
 mod instrs;
+mod optimizer;
 mod process;
+mod scheduler;
 mod state;
+mod status;
+mod trap;
+mod verifier;
 mod vm;
 
 
-pub use instrs::Instr;
+pub use analysis::{analyze, Analysis, StackError, StackErrorKind};
+pub use assembler::{assemble, AssembleError, AssembleErrorKind};
+pub use builder::{ProgramBuilder, Label, BuildError, OperandValue};
+pub use decoder::{decode, Decoded, Operand, DecodeError, DecodeErrorKind};
+pub use disassembler::disassemble;
+pub use div_mode::DivMode;
+pub use encoding::Encoding;
+pub use fuel::CostTable;
+pub use instrs::{Instr, InvalidOpcode, ValueType, OperandKind, StackEffect};
+pub use optimizer::{optimize, OptimizeError, OptimizeErrorKind};
 pub use process::Process;
+pub use scheduler::{Scheduler, Pid};
 pub use state::State;
-pub use vm::run;
+pub use status::Status;
+pub use trap::{Trap, TrapKind};
+pub use verifier::{verify, VerifyError, VerifyErrorKind};
+pub use vm::{run, run_for, step};
@@ -0,0 +1,260 @@
+use core::fmt;
+
+use vector::Vector;
+use stack::Stack;
+
+use instrs::{Instr, OperandKind};
+use decoder::{self, Decoded, DecodeErrorKind};
+use builder::{ProgramBuilder, Label};
+use encoding;
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OptimizeErrorKind {
+    // instruction does not decode
+    Undecodable(DecodeErrorKind),
+    // branch target is read from memory or popped from the stack, the
+    // instructions cannot be moved
+    UnknownTarget,
+    // branch target is not the offset of an instruction
+    InvalidTarget(usize),
+}
+
+impl fmt::Display for OptimizeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OptimizeErrorKind::Undecodable(kind) => write!(f, "{}", kind),
+            OptimizeErrorKind::UnknownTarget => write!(f, "unknown jump target"),
+            OptimizeErrorKind::InvalidTarget(target) => write!(f, "invalid jump target {}", target),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct OptimizeError {
+    kind: OptimizeErrorKind,
+    offset: usize,
+}
+
+impl OptimizeError {
+    #[inline]
+    pub fn new(kind: OptimizeErrorKind, offset: usize) -> Self {
+        OptimizeError {
+            kind: kind,
+            offset: offset,
+        }
+    }
+
+    #[inline]
+    pub fn get_kind(&self) -> OptimizeErrorKind {self.kind}
+    #[inline]
+    pub fn get_offset(&self) -> usize {self.offset}
+}
+
+impl fmt::Display for OptimizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.offset)
+    }
+}
+
+
+// branch fused from compare followed by if_jmp
+#[inline]
+fn fused(compare: Instr) -> Option<Instr> {
+    match compare {
+        Instr::eq_u8 => Some(Instr::br_eq_u8),
+        Instr::eq_u16 => Some(Instr::br_eq_u16),
+        Instr::eq_u32 => Some(Instr::br_eq_u32),
+        Instr::eq_u64 => Some(Instr::br_eq_u64),
+        Instr::eq_i8 => Some(Instr::br_eq_i8),
+        Instr::eq_i16 => Some(Instr::br_eq_i16),
+        Instr::eq_i32 => Some(Instr::br_eq_i32),
+        Instr::eq_i64 => Some(Instr::br_eq_i64),
+        Instr::eq_f32 => Some(Instr::br_eq_f32),
+        Instr::eq_f64 => Some(Instr::br_eq_f64),
+        Instr::lt_u8 => Some(Instr::br_lt_u8),
+        Instr::lt_u16 => Some(Instr::br_lt_u16),
+        Instr::lt_u32 => Some(Instr::br_lt_u32),
+        Instr::lt_u64 => Some(Instr::br_lt_u64),
+        Instr::lt_i8 => Some(Instr::br_lt_i8),
+        Instr::lt_i16 => Some(Instr::br_lt_i16),
+        Instr::lt_i32 => Some(Instr::br_lt_i32),
+        Instr::lt_i64 => Some(Instr::br_lt_i64),
+        Instr::lt_f32 => Some(Instr::br_lt_f32),
+        Instr::lt_f64 => Some(Instr::br_lt_f64),
+        _ => None,
+    }
+}
+
+// branch skipping a jmp, fused from neq followed by if_jmp, there is no
+// br_neq, so jumping when the values differ means jumping over the jmp when
+// they are equal
+#[inline]
+fn fused_inverted(compare: Instr) -> Option<Instr> {
+    match compare {
+        Instr::neq_u8 => Some(Instr::br_eq_u8),
+        Instr::neq_u16 => Some(Instr::br_eq_u16),
+        Instr::neq_u32 => Some(Instr::br_eq_u32),
+        Instr::neq_u64 => Some(Instr::br_eq_u64),
+        Instr::neq_i8 => Some(Instr::br_eq_i8),
+        Instr::neq_i16 => Some(Instr::br_eq_i16),
+        Instr::neq_i32 => Some(Instr::br_eq_i32),
+        Instr::neq_i64 => Some(Instr::br_eq_i64),
+        Instr::neq_f32 => Some(Instr::br_eq_f32),
+        Instr::neq_f64 => Some(Instr::br_eq_f64),
+        _ => None,
+    }
+}
+
+// branch fused from a push of 0, compare and if_jmp
+#[inline]
+fn fused_zero(compare: Instr) -> Option<Instr> {
+    match compare {
+        Instr::eq_u8 => Some(Instr::br_zero_u8),
+        Instr::eq_u16 => Some(Instr::br_zero_u16),
+        Instr::eq_u32 => Some(Instr::br_zero_u32),
+        Instr::eq_u64 => Some(Instr::br_zero_u64),
+        Instr::eq_i8 => Some(Instr::br_zero_i8),
+        Instr::eq_i16 => Some(Instr::br_zero_i16),
+        Instr::eq_i32 => Some(Instr::br_zero_i32),
+        Instr::eq_i64 => Some(Instr::br_zero_i64),
+        Instr::eq_f32 => Some(Instr::br_zero_f32),
+        Instr::eq_f64 => Some(Instr::br_zero_f64),
+        Instr::neq_u8 => Some(Instr::br_nonzero_u8),
+        Instr::neq_u16 => Some(Instr::br_nonzero_u16),
+        Instr::neq_u32 => Some(Instr::br_nonzero_u32),
+        Instr::neq_u64 => Some(Instr::br_nonzero_u64),
+        Instr::neq_i8 => Some(Instr::br_nonzero_i8),
+        Instr::neq_i16 => Some(Instr::br_nonzero_i16),
+        Instr::neq_i32 => Some(Instr::br_nonzero_i32),
+        Instr::neq_i64 => Some(Instr::br_nonzero_i64),
+        Instr::neq_f32 => Some(Instr::br_nonzero_f32),
+        Instr::neq_f64 => Some(Instr::br_nonzero_f64),
+        _ => None,
+    }
+}
+
+// push of a type_int 0 of the type compare takes
+#[inline]
+fn is_zero_push(decoded: &Decoded, compare: Instr) -> bool {
+    let push = decoded.get_instr();
+
+    match decoded.get_operand() {
+        Some(operand) if push.operands() == &[OperandKind::Immediate][..] => {
+            push.value_type() == compare.value_type() && operand.get_mode() == Instr::type_int &&
+                operand.get_typed_value(compare.value_type().unwrap()) == 0
+        },
+        _ => false,
+    }
+}
+
+// fused branch replacing the instructions at index, how many it replaces
+// and whether it is inverted, None if they do not form a compare and if_jmp
+// no other branch jumps into
+fn fuse(decoded: &[Decoded], labels: &[Option<Label>], index: usize) -> Option<(Instr, usize, bool)> {
+    let untargeted = |index: usize| labels[decoded[index].get_offset()].is_none();
+    let is_if_jmp = |index: usize| index < decoded.len() && decoded[index].get_instr() == Instr::if_jmp && untargeted(index);
+
+    if is_if_jmp(index + 2) && untargeted(index + 1) {
+        let compare = decoded[index + 1].get_instr();
+
+        if let Some(branch) = fused_zero(compare) {
+            if is_zero_push(&decoded[index], compare) {
+                return Some((branch, 3, false));
+            }
+        }
+    }
+    if is_if_jmp(index + 1) {
+        if let Some(branch) = fused(decoded[index].get_instr()) {
+            return Some((branch, 2, false));
+        }
+        if let Some(branch) = fused_inverted(decoded[index].get_instr()) {
+            return Some((branch, 2, true));
+        }
+    }
+    None
+}
+
+
+// rewrites program with compare and if_jmp pairs, and 0 compared by eq or
+// neq before if_jmp, replaced by the fused br_* branches, a neq and if_jmp
+// pair becomes a br_eq over a jmp, every branch
+// target moves with its instruction, so programs computing targets with
+// jmp_indirect or call_indirect, or reading them from memory, are rejected
+pub fn optimize(program: &[u8]) -> Result<Vector<u8>, OptimizeError> {
+    let (encoding, start) = encoding::read_header(program);
+
+    let mut decoded = Vector::new();
+    let mut starts = Vector::with_capacity(program.len() + 1);
+    for _ in 0..program.len() + 1 {
+        starts.push(false);
+    }
+    let mut offset = start;
+    while offset < program.len() {
+        let instr = match decoder::decode(program, offset) {
+            Ok(instr) => instr,
+            Err(error) => return Err(OptimizeError::new(OptimizeErrorKind::Undecodable(error.get_kind()), error.get_offset())),
+        };
+        starts[offset] = true;
+        offset = instr.get_end();
+        decoded.push(instr);
+    }
+    starts[program.len()] = true;
+
+    // a label for every branch target
+    let mut builder = if start > 0 {ProgramBuilder::with_encoding(encoding)} else {ProgramBuilder::new()};
+    let mut labels: Vector<Option<Label>> = Vector::with_capacity(program.len() + 1);
+    for _ in 0..program.len() + 1 {
+        labels.push(None);
+    }
+    for instr in decoded.iter() {
+        match instr.get_instr() {
+            Instr::jmp_indirect | Instr::call_indirect => {
+                return Err(OptimizeError::new(OptimizeErrorKind::UnknownTarget, instr.get_offset()));
+            },
+            _ if !decoder::is_branch(instr.get_instr()) => continue,
+            _ => (),
+        }
+
+        match instr.get_target() {
+            Some(target) if target < starts.len() && starts[target] => if labels[target].is_none() {
+                labels[target] = Some(builder.label());
+            },
+            Some(target) => return Err(OptimizeError::new(OptimizeErrorKind::InvalidTarget(target), instr.get_offset())),
+            None => return Err(OptimizeError::new(OptimizeErrorKind::UnknownTarget, instr.get_offset())),
+        }
+    }
+
+    let mut index = 0;
+    while index < decoded.len() {
+        let instr = decoded[index];
+        if let Some(label) = labels[instr.get_offset()] {
+            builder.bind(label);
+        }
+
+        match fuse(&decoded, &labels, index) {
+            Some((branch, count, inverted)) => {
+                let if_jmp = decoded[index + count - 1];
+                let target = labels[if_jmp.get_target().unwrap()].unwrap();
+
+                if inverted {
+                    let skip = builder.label();
+                    builder.with_label(branch, skip).with_label(Instr::jmp, target).bind(skip);
+                } else {
+                    builder.with_label(branch, target);
+                }
+                index += count;
+            },
+            None => {
+                let target = instr.get_target().and_then(|target| labels[target]);
+                builder.with_decoded(&instr, target);
+                index += 1;
+            },
+        }
+    }
+    if let Some(label) = labels[program.len()] {
+        builder.bind(label);
+    }
+
+    Ok(builder.build().expect("every branch target is labeled"))
+}
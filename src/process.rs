@@ -1,10 +1,25 @@
-use core::mem;
+use core::convert::TryFrom;
+use core::cmp;
+use core::ptr;
 
 use vector::Vector;
 use stack::Stack;
 
 use instrs::Instr;
+use encoding::{self, Encoding, VarintError};
 use state::State;
+use div_mode::DivMode;
+use fuel::CostTable;
+use trap::{Trap, TrapKind};
+
+
+// state of the caller, saved by call and restored by ret
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    return_address: usize,
+    frame_pointer: usize,
+    locals_end: usize,
+}
 
 
 #[derive(Debug)]
@@ -12,26 +27,84 @@ pub struct Process<'a> {
     state: State,
 
     program_counter: usize,
+    // start of the instruction being evaluated, reported by traps
+    instr_counter: usize,
     program: &'a [u8],
+    // operand encoding selected by the program header
+    encoding: Encoding,
 
     stack: Vector<u8>,
-    function_stack: Vector<usize>,
+    function_stack: Vector<Frame>,
+    // stack index of the current frame, its arguments followed by its locals
+    frame_pointer: usize,
+    // stack index after the frame's locals, ret keeps what lies above
+    locals_end: usize,
+    // sorted offsets jmp_indirect and call_indirect may target, any if None
+    entry_points: Option<Vector<usize>>,
+
+    // linear memory, addresses are offsets into this buffer
+    memory: Vector<u8>,
+    // addresses are host pointers, see set_raw_memory
+    raw_memory: bool,
+
+    div_mode: DivMode,
+    // set by halt and halt_with
+    exit_code: Option<u32>,
+    // trap that terminated the process
+    trap: Option<Trap>,
+    // set by pause until the caller of vm::run_for takes it
+    paused: bool,
+    // event wait blocks on while Waiting
+    event: Option<u64>,
+    // start and stack of the child set by spawn until the caller of
+    // vm::run_for takes it
+    spawned: Option<(usize, Vector<u8>)>,
+
+    // charged for every instruction, unmetered if None
+    costs: Option<CostTable>,
+    fuel: u64,
 }
 
 impl<'a> Process<'a> {
 
     #[inline]
     pub fn new(program: &'a [u8]) -> Self {
+        let (encoding, start) = encoding::read_header(program);
+
         Process {
             state: State::New,
 
-            program_counter: 0,
+            program_counter: start,
+            instr_counter: start,
             program: program,
+            encoding: encoding,
 
             stack: Vector::new(),
             function_stack: Vector::new(),
+            frame_pointer: 0,
+            locals_end: 0,
+            entry_points: None,
+
+            memory: Vector::new(),
+            raw_memory: false,
+
+            div_mode: DivMode::Trap,
+            exit_code: None,
+            trap: None,
+            paused: false,
+            event: None,
+            spawned: None,
+
+            costs: None,
+            fuel: 0,
         }
     }
+    #[inline]
+    pub fn with_memory(program: &'a [u8], memory_size: usize) -> Self {
+        let mut process = Self::new(program);
+        process.grow_memory(memory_size);
+        process
+    }
 
     #[inline]
     pub fn get_state(&self) -> State {self.state}
@@ -40,10 +113,220 @@ impl<'a> Process<'a> {
         self.state = state;
     }
 
+    #[inline]
+    pub fn get_program_counter(&self) -> usize {self.program_counter}
+    #[inline]
+    pub fn get_instr_counter(&self) -> usize {self.instr_counter}
+    #[inline]
+    pub fn get_encoding(&self) -> Encoding {self.encoding}
+    #[inline]
+    pub fn get_frame_pointer(&self) -> usize {self.frame_pointer}
+    // calls not yet returned from
+    #[inline]
+    pub fn get_call_depth(&self) -> usize {self.function_stack.len()}
+
     #[inline]
     pub fn is_running(&self) -> bool {self.state == State::Running}
+    // exit code of the last halt or halt_with, None if neither ran
+    #[inline]
+    pub fn get_exit_code(&self) -> Option<u32> {self.exit_code}
+    // trap that terminated the process, None if it halted, finished or runs
+    #[inline]
+    pub fn get_trap(&self) -> Option<Trap> {self.trap}
+    // stops the process for good, vm::run returns trap from then on
+    #[inline]
+    pub fn terminate(&mut self, trap: Trap) {
+        self.trap = Some(trap);
+        self.state = State::Terminated;
+    }
+    // event the process waits on, None unless Waiting
+    #[inline]
+    pub fn get_event(&self) -> Option<u64> {self.event}
+
+    // lets a Waiting process run again, it continues after its wait
+    #[inline]
+    pub fn wake(&mut self) {
+        if self.state == State::Waiting {
+            self.event = None;
+            self.state = State::Running;
+        }
+    }
+    // whether pause ran since the last call, clears it
+    #[inline]
+    pub fn take_paused(&mut self) -> bool {
+        let paused = self.paused;
+        self.paused = false;
+        paused
+    }
+
+    // whether spawn ran and its child was not taken yet
+    #[inline]
+    pub fn is_spawning(&self) -> bool {self.spawned.is_some()}
+    // child of the last spawn, clears it, the child shares the program,
+    // entry points and division mode and gets a copy of the cost table, not
+    // the memory, and starts without fuel
+    #[inline]
+    pub fn take_spawned(&mut self) -> Option<Process<'a>> {
+        let (start, stack) = self.spawned.take()?;
+
+        let mut child = Process::new(self.program);
+        child.program_counter = start;
+        child.instr_counter = start;
+        child.stack = stack;
+        if let Some(ref entry_points) = self.entry_points {
+            child.set_entry_points(entry_points);
+        }
+        child.raw_memory = self.raw_memory;
+        child.div_mode = self.div_mode;
+        child.costs = self.costs.clone();
+        Some(child)
+    }
+
+    #[inline]
+    pub fn get_memory(&self) -> &[u8] {&self.memory}
+    #[inline]
+    pub fn get_memory_mut(&mut self) -> &mut [u8] {&mut self.memory}
+    #[inline]
+    pub fn get_memory_size(&self) -> usize {self.memory.len()}
+
+    // grows memory by size zeroed bytes, returns the previous size
+    #[inline]
+    pub fn grow_memory(&mut self, size: usize) -> usize {
+        let previous_size = self.memory.len();
+        for _ in 0..size {
+            self.memory.push(0);
+        }
+        previous_size
+    }
+
+    /// treats every address as a host pointer instead of an offset into
+    /// process memory, values are big-endian and need not be aligned
+    ///
+    /// # Safety
+    ///
+    /// while raw memory is on, every address the program loads from or
+    /// saves to must point to memory that is valid for reads or writes of
+    /// the value's width, and stays allocated and unaliased by Rust
+    /// references for as long as the process runs, children started by
+    /// spawn inherit the setting
+    #[inline]
+    pub unsafe fn set_raw_memory(&mut self, raw_memory: bool) {
+        self.raw_memory = raw_memory;
+    }
+    #[inline]
+    pub fn is_raw_memory(&self) -> bool {self.raw_memory}
+
+    #[inline]
+    pub fn get_div_mode(&self) -> DivMode {self.div_mode}
+    #[inline]
+    pub fn set_div_mode(&mut self, div_mode: DivMode) {
+        self.div_mode = div_mode;
+    }
+
+    #[inline]
+    pub fn get_entry_points(&self) -> Option<&[usize]> {self.entry_points.as_deref()}
+    // restricts jmp_indirect and call_indirect to the given offsets
+    #[inline]
+    pub fn set_entry_points(&mut self, entry_points: &[usize]) {
+        let mut sorted = Vector::with_capacity(entry_points.len());
+        for entry_point in entry_points.iter() {
+            sorted.push(*entry_point);
+        }
+        sorted.sort_unstable();
+        self.entry_points = Some(sorted);
+    }
+    // lets jmp_indirect and call_indirect target any offset
+    #[inline]
+    pub fn clear_entry_points(&mut self) {
+        self.entry_points = None;
+    }
+
+    #[inline]
+    pub fn get_cost_table(&self) -> Option<&CostTable> {self.costs.as_ref()}
+    // meters the process, every instruction is paid for from its fuel
+    #[inline]
+    pub fn set_cost_table(&mut self, costs: CostTable) {
+        self.costs = Some(costs);
+    }
+    #[inline]
+    pub fn clear_cost_table(&mut self) {
+        self.costs = None;
+    }
+
+    #[inline]
+    pub fn get_fuel(&self) -> u64 {self.fuel}
+    #[inline]
+    pub fn set_fuel(&mut self, fuel: u64) {
+        self.fuel = fuel;
+    }
+    // adds fuel, saturating, returns the fuel left
+    #[inline]
+    pub fn refuel(&mut self, fuel: u64) -> u64 {
+        self.fuel = self.fuel.saturating_add(fuel);
+        self.fuel
+    }
+
+    // instruction at the program counter, None past the end or for an
+    // invalid opcode
+    #[inline]
+    fn instr_at(&self, index: usize) -> Option<Instr> {
+        match self.program.get(index).map(|opcode| Instr::try_from(*opcode)) {
+            Some(Ok(Instr::ext)) => match self.program.get(index + 1) {
+                Some(opcode) => Instr::from_extended(*opcode).ok(),
+                None => None,
+            },
+            Some(Ok(instr)) => Some(instr),
+            _ => None,
+        }
+    }
+    // pays for the instruction at the program counter before it is
+    // evaluated, a prefix is paid for together with the instruction it
+    // prefixes, traps at the instruction leaving it to run once refueled
+    #[inline]
+    pub fn consume_fuel(&mut self) -> Result<(), Trap> {
+        let costs = match self.costs {
+            Some(ref costs) => costs,
+            None => return Ok(()),
+        };
+        let index = self.program_counter;
+
+        let cost = match self.instr_at(index) {
+            Some(instr @ Instr::checked) | Some(instr @ Instr::sat) => {
+                let prefixed = self.instr_at(index + 1).map_or(0, |prefixed| costs.instr_cost(prefixed));
+                costs.instr_cost(instr).saturating_add(prefixed)
+            },
+            Some(instr) => costs.instr_cost(instr),
+            // evaluating it stops the process
+            None => 0,
+        };
+
+        if cost > self.fuel {
+            self.instr_counter = index;
+            return Err(self.trap(TrapKind::OutOfFuel));
+        }
+        self.fuel -= cost;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn trap(&self, kind: TrapKind) -> Trap {
+        Trap::new(kind, self.instr_counter)
+    }
+
     #[inline]
-    pub fn to_instr(instruction: u8) -> Instr {unsafe {mem::transmute(instruction)}}
+    fn divide_by_zero(&self) -> Result<(), Trap> {
+        match self.div_mode {
+            DivMode::Trap => Err(self.trap(TrapKind::DivideByZero)),
+            DivMode::Wrap => Ok(()),
+        }
+    }
+    #[inline]
+    fn divide_overflow(&self) -> Result<(), Trap> {
+        match self.div_mode {
+            DivMode::Trap => Err(self.trap(TrapKind::Overflow)),
+            DivMode::Wrap => Ok(()),
+        }
+    }
 
     #[inline]
     pub fn next(&mut self) -> Option<u8> {
@@ -55,9 +338,17 @@ impl<'a> Process<'a> {
             None
         }
     }
+    #[inline]
+    pub fn next_instr(&mut self) -> Option<u8> {
+        self.instr_counter = self.program_counter;
+        self.next()
+    }
     #[inline(always)]
-    pub fn next_expect(&mut self) -> u8 {
-        self.next().expect("Unexpected end of program")
+    pub fn next_expect(&mut self) -> Result<u8, Trap> {
+        match self.next() {
+            Some(data) => Ok(data),
+            None => Err(self.trap(TrapKind::TruncatedProgram)),
+        }
     }
 
     #[inline(always)]
@@ -85,1492 +376,3286 @@ impl<'a> Process<'a> {
     }
 
     #[inline]
-    pub fn next_u8(&mut self) -> u8 {
+    pub fn next_u8(&mut self) -> Result<u8, Trap> {
         self.next_expect()
     }
     #[inline]
-    pub fn next_u16(&mut self) -> u16 {
-        Self::to_u16(
-            self.next_expect(),
-            self.next_expect()
-        )
+    pub fn next_u16(&mut self) -> Result<u16, Trap> {
+        Ok(Self::to_u16(
+            self.next_expect()?,
+            self.next_expect()?
+        ))
     }
     #[inline]
-    pub fn next_u32(&mut self) -> u32 {
-        Self::to_u32(
-            self.next_expect(),
-            self.next_expect(),
-            self.next_expect(),
-            self.next_expect()
-        )
+    pub fn next_u32(&mut self) -> Result<u32, Trap> {
+        Ok(Self::to_u32(
+            self.next_expect()?,
+            self.next_expect()?,
+            self.next_expect()?,
+            self.next_expect()?
+        ))
     }
     #[inline]
-    pub fn next_u64(&mut self) -> u64 {
-        Self::to_u64(
-            self.next_expect(),
-            self.next_expect(),
-            self.next_expect(),
-            self.next_expect(),
-            self.next_expect(),
-            self.next_expect(),
-            self.next_expect(),
-            self.next_expect()
-        )
+    pub fn next_u64(&mut self) -> Result<u64, Trap> {
+        Ok(Self::to_u64(
+            self.next_expect()?,
+            self.next_expect()?,
+            self.next_expect()?,
+            self.next_expect()?,
+            self.next_expect()?,
+            self.next_expect()?,
+            self.next_expect()?,
+            self.next_expect()?
+        ))
     }
 
     #[cfg(target_pointer_width = "32")]
     #[inline]
-    pub fn next_usize(&mut self) -> usize {self.next_u32() as usize}
+    pub fn next_usize(&mut self) -> Result<usize, Trap> {Ok(self.next_u32()? as usize)}
     #[cfg(target_pointer_width = "64")]
     #[inline]
-    pub fn next_usize(&mut self) -> usize {self.next_u64() as usize}
+    pub fn next_usize(&mut self) -> Result<usize, Trap> {Ok(self.next_u64()? as usize)}
 
     #[inline]
-    pub fn next_i8(&mut self) -> i8 {self.next_u8() as i8}
+    pub fn next_i8(&mut self) -> Result<i8, Trap> {Ok(self.next_u8()? as i8)}
     #[inline]
-    pub fn next_i16(&mut self) -> i16 {self.next_u16() as i16}
+    pub fn next_i16(&mut self) -> Result<i16, Trap> {Ok(self.next_u16()? as i16)}
     #[inline]
-    pub fn next_i32(&mut self) -> i32 {self.next_u32() as i32}
+    pub fn next_i32(&mut self) -> Result<i32, Trap> {Ok(self.next_u32()? as i32)}
     #[inline]
-    pub fn next_i64(&mut self) -> i64 {self.next_u64() as i64}
+    pub fn next_i64(&mut self) -> Result<i64, Trap> {Ok(self.next_u64()? as i64)}
 
     #[cfg(target_pointer_width = "32")]
     #[inline]
-    pub fn next_isize(&mut self) -> usize {self.next_i32() as usize}
+    pub fn next_isize(&mut self) -> Result<usize, Trap> {Ok(self.next_i32()? as usize)}
     #[cfg(target_pointer_width = "64")]
     #[inline]
-    pub fn next_isize(&mut self) -> usize {self.next_i64() as usize}
+    pub fn next_isize(&mut self) -> Result<usize, Trap> {Ok(self.next_i64()? as usize)}
+
+    #[inline]
+    pub fn next_f32(&mut self) -> Result<f32, Trap> {Ok(f32::from_bits(self.next_u32()?))}
+    #[inline]
+    pub fn next_f64(&mut self) -> Result<f64, Trap> {Ok(f64::from_bits(self.next_u64()?))}
+
+    // LEB128 operand of the varint encoding
+    #[inline]
+    pub fn read_varint(&mut self) -> Result<u64, Trap> {
+        match encoding::read_varint(self.program, self.program_counter) {
+            Ok((value, end)) => {
+                self.program_counter = end;
+                Ok(value)
+            },
+            Err(VarintError::Truncated) => Err(self.trap(TrapKind::TruncatedProgram)),
+            Err(VarintError::Overlong) => Err(self.trap(TrapKind::InvalidVarint)),
+        }
+    }
+
+    #[inline]
+    pub fn read_size_8(&mut self) -> Result<u8, Trap> {
+        if self.encoding == Encoding::Varint {
+            return Ok(self.read_varint()? as u8);
+        }
+        let size = self.next_u8()?;
+        match Instr::try_from(size) {
+            Ok(Instr::size_8) => self.next_u8(),
+            Ok(Instr::size_16) => Ok(self.next_u16()? as u8),
+            Ok(Instr::size_32) => Ok(self.next_u32()? as u8),
+            Ok(Instr::size_64) => Ok(self.next_u64()? as u8),
+            _ => Err(self.trap(TrapKind::InvalidSize(size))),
+        }
+    }
+    #[inline]
+    pub fn read_size_16(&mut self) -> Result<u16, Trap> {
+        if self.encoding == Encoding::Varint {
+            return Ok(self.read_varint()? as u16);
+        }
+        let size = self.next_u8()?;
+        match Instr::try_from(size) {
+            Ok(Instr::size_8) => Ok(self.next_u8()? as u16),
+            Ok(Instr::size_16) => self.next_u16(),
+            Ok(Instr::size_32) => Ok(self.next_u32()? as u16),
+            Ok(Instr::size_64) => Ok(self.next_u64()? as u16),
+            _ => Err(self.trap(TrapKind::InvalidSize(size))),
+        }
+    }
+    #[inline]
+    pub fn read_size_32(&mut self) -> Result<u32, Trap> {
+        if self.encoding == Encoding::Varint {
+            return Ok(self.read_varint()? as u32);
+        }
+        let size = self.next_u8()?;
+        match Instr::try_from(size) {
+            Ok(Instr::size_8) => Ok(self.next_u8()? as u32),
+            Ok(Instr::size_16) => Ok(self.next_u16()? as u32),
+            Ok(Instr::size_32) => self.next_u32(),
+            Ok(Instr::size_64) => Ok(self.next_u64()? as u32),
+            _ => Err(self.trap(TrapKind::InvalidSize(size))),
+        }
+    }
+    #[inline]
+    pub fn read_size_64(&mut self) -> Result<u64, Trap> {
+        if self.encoding == Encoding::Varint {
+            return self.read_varint();
+        }
+        let size = self.next_u8()?;
+        match Instr::try_from(size) {
+            Ok(Instr::size_8) => Ok(self.next_u8()? as u64),
+            Ok(Instr::size_16) => Ok(self.next_u16()? as u64),
+            Ok(Instr::size_32) => Ok(self.next_u32()? as u64),
+            Ok(Instr::size_64) => self.next_u64(),
+            _ => Err(self.trap(TrapKind::InvalidSize(size))),
+        }
+    }
 
+    #[cfg(target_pointer_width = "32")]
+    #[inline]
+    pub fn read_size_usize(&mut self) -> Result<usize, Trap> {Ok(self.read_size_32()? as usize)}
+    #[cfg(target_pointer_width = "64")]
     #[inline]
-    pub fn next_f32(&mut self) -> f32 {self.next_u32() as f32}
+    pub fn read_size_usize(&mut self) -> Result<usize, Trap> {Ok(self.read_size_64()? as usize)}
+
     #[inline]
-    pub fn next_f64(&mut self) -> f64 {self.next_u64() as f64}
+    fn check_memory(&self, address: usize, size: usize) -> Result<usize, Trap> {
+        match address.checked_add(size) {
+            Some(end) if end <= self.memory.len() => Ok(address),
+            _ => Err(self.trap(TrapKind::MemoryOutOfBounds(address))),
+        }
+    }
 
     #[inline]
-    pub fn read_size_8(&mut self) -> u8 {
-        match Self::to_instr(self.next_u8()) {
-            Instr::size_8 => self.next_u8(),
-            Instr::size_16 => self.next_u16() as u8,
-            Instr::size_32 => self.next_u32() as u8,
-            Instr::size_64 => self.next_u64() as u8,
-            instr => panic!("Invalid size {:?} given", instr),
+    pub fn read_mem_u8(&self, address: usize) -> Result<u8, Trap> {
+        if self.raw_memory {
+            Ok(unsafe {*(address as *const u8)})
+        } else {
+            let index = self.check_memory(address, 1)?;
+            Ok(self.memory[index])
         }
     }
     #[inline]
-    pub fn read_size_16(&mut self) -> u16 {
-        match Self::to_instr(self.next_u8()) {
-            Instr::size_8 => self.next_u8() as u16,
-            Instr::size_16 => self.next_u16(),
-            Instr::size_32 => self.next_u32() as u16,
-            Instr::size_64 => self.next_u64() as u16,
-            instr => panic!("Invalid size {:?} given", instr),
+    pub fn read_mem_u16(&self, address: usize) -> Result<u16, Trap> {
+        if self.raw_memory {
+            Ok(u16::from_be(unsafe {ptr::read_unaligned(address as *const u16)}))
+        } else {
+            let index = self.check_memory(address, 2)?;
+            let ref memory = self.memory;
+            Ok(Self::to_u16(memory[index], memory[index + 1]))
         }
     }
     #[inline]
-    pub fn read_size_32(&mut self) -> u32 {
-        match Self::to_instr(self.next_u8()) {
-            Instr::size_8 => self.next_u8() as u32,
-            Instr::size_16 => self.next_u16() as u32,
-            Instr::size_32 => self.next_u32(),
-            Instr::size_64 => self.next_u64() as u32,
-            instr => panic!("Invalid size {:?} given", instr),
+    pub fn read_mem_u32(&self, address: usize) -> Result<u32, Trap> {
+        if self.raw_memory {
+            Ok(u32::from_be(unsafe {ptr::read_unaligned(address as *const u32)}))
+        } else {
+            let index = self.check_memory(address, 4)?;
+            let ref memory = self.memory;
+            Ok(Self::to_u32(
+                memory[index], memory[index + 1], memory[index + 2], memory[index + 3]
+            ))
         }
     }
     #[inline]
-    pub fn read_size_64(&mut self) -> u64 {
-        match Self::to_instr(self.next_u8()) {
-            Instr::size_8 => self.next_u8() as u64,
-            Instr::size_16 => self.next_u16() as u64,
-            Instr::size_32 => self.next_u32() as u64,
-            Instr::size_64 => self.next_u64(),
-            instr => panic!("Invalid size {:?} given", instr),
+    pub fn read_mem_u64(&self, address: usize) -> Result<u64, Trap> {
+        if self.raw_memory {
+            Ok(u64::from_be(unsafe {ptr::read_unaligned(address as *const u64)}))
+        } else {
+            let index = self.check_memory(address, 8)?;
+            let ref memory = self.memory;
+            Ok(Self::to_u64(
+                memory[index], memory[index + 1], memory[index + 2], memory[index + 3],
+                memory[index + 4], memory[index + 5], memory[index + 6], memory[index + 7]
+            ))
         }
     }
 
     #[cfg(target_pointer_width = "32")]
     #[inline]
-    pub fn read_size_usize(&mut self) -> usize {self.read_size_32() as usize}
+    pub fn read_mem_usize(&self, address: usize) -> Result<usize, Trap> {Ok(self.read_mem_u32(address)? as usize)}
     #[cfg(target_pointer_width = "64")]
     #[inline]
-    pub fn read_size_usize(&mut self) -> usize {self.read_size_64() as usize}
+    pub fn read_mem_usize(&self, address: usize) -> Result<usize, Trap> {Ok(self.read_mem_u64(address)? as usize)}
+
+    #[inline]
+    pub fn write_mem_u8(&mut self, address: usize, value: u8) -> Result<(), Trap> {
+        if self.raw_memory {
+            unsafe {*(address as *mut u8) = value};
+        } else {
+            let index = self.check_memory(address, 1)?;
+            self.memory[index] = value;
+        }
+        Ok(())
+    }
+    #[inline]
+    pub fn write_mem_u16(&mut self, address: usize, value: u16) -> Result<(), Trap> {
+        if self.raw_memory {
+            unsafe {ptr::write_unaligned(address as *mut u16, value.to_be())};
+        } else {
+            let index = self.check_memory(address, 2)?;
+            let ref mut memory = self.memory;
+            memory[index] = (value >> 8) as u8;
+            memory[index + 1] = value as u8;
+        }
+        Ok(())
+    }
+    #[inline]
+    pub fn write_mem_u32(&mut self, address: usize, value: u32) -> Result<(), Trap> {
+        if self.raw_memory {
+            unsafe {ptr::write_unaligned(address as *mut u32, value.to_be())};
+        } else {
+            let index = self.check_memory(address, 4)?;
+            let ref mut memory = self.memory;
+            memory[index] = (value >> 24) as u8;
+            memory[index + 1] = (value >> 16) as u8;
+            memory[index + 2] = (value >> 8) as u8;
+            memory[index + 3] = value as u8;
+        }
+        Ok(())
+    }
+    #[inline]
+    pub fn write_mem_u64(&mut self, address: usize, value: u64) -> Result<(), Trap> {
+        if self.raw_memory {
+            unsafe {ptr::write_unaligned(address as *mut u64, value.to_be())};
+        } else {
+            let index = self.check_memory(address, 8)?;
+            let ref mut memory = self.memory;
+            memory[index] = (value >> 56) as u8;
+            memory[index + 1] = (value >> 48) as u8;
+            memory[index + 2] = (value >> 40) as u8;
+            memory[index + 3] = (value >> 32) as u8;
+            memory[index + 4] = (value >> 24) as u8;
+            memory[index + 5] = (value >> 16) as u8;
+            memory[index + 6] = (value >> 8) as u8;
+            memory[index + 7] = value as u8;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn offset_address(&self, address: usize, offset: usize) -> Result<usize, Trap> {
+        match address.checked_add(offset) {
+            Some(address) => Ok(address),
+            None => Err(self.trap(TrapKind::MemoryOutOfBounds(address))),
+        }
+    }
 
     #[inline]
-    pub fn read_u8(&mut self) -> u8 {
-        match Self::to_instr(self.next_u8()) {
-            Instr::type_int => self.read_size_8(),
+    pub fn read_u8(&mut self) -> Result<u8, Trap> {
+        let kind = self.next_u8()?;
+        match Instr::try_from(kind) {
+            Ok(Instr::type_int) => self.read_size_8(),
 
-            Instr::type_ptr => unsafe {*(self.read_size_usize() as *const u8)},
-            Instr::type_ptr_off => unsafe {*((self.read_size_usize() + self.read_size_usize()) as *const u8)},
+            Ok(Instr::type_ptr) => {
+                let address = self.read_size_usize()?;
+                self.read_mem_u8(address)
+            },
+            Ok(Instr::type_ptr_off) => {
+                let address = self.read_size_usize()?;
+                let offset = self.read_size_usize()?;
+                let address = self.offset_address(address, offset)?;
+                self.read_mem_u8(address)
+            },
 
-            Instr::type_idr_ptr => unsafe {**(self.read_size_usize() as *const *const u8)},
-            Instr::type_idr_ptr_off => unsafe {
-                *((*(self.read_size_usize() as *const usize) + self.read_size_usize()) as *const u8)
+            Ok(Instr::type_idr_ptr) => {
+                let address = self.read_size_usize()?;
+                let address = self.read_mem_usize(address)?;
+                self.read_mem_u8(address)
+            },
+            Ok(Instr::type_idr_ptr_off) => {
+                let address = self.read_size_usize()?;
+                let offset = self.read_size_usize()?;
+                let address = self.read_mem_usize(address)?;
+                let address = self.offset_address(address, offset)?;
+                self.read_mem_u8(address)
             },
 
-            instr => panic!("Invalid type {:?} given, trying to read 8 bits ", instr),
+            _ => Err(self.trap(TrapKind::InvalidType(kind))),
         }
     }
     #[inline]
-    pub fn read_u16(&mut self) -> u16 {
-        match Self::to_instr(self.next_u8()) {
-            Instr::type_int => self.read_size_16(),
+    pub fn read_u16(&mut self) -> Result<u16, Trap> {
+        let kind = self.next_u8()?;
+        match Instr::try_from(kind) {
+            Ok(Instr::type_int) => self.read_size_16(),
 
-            Instr::type_ptr => unsafe {*(self.read_size_usize() as *const u16)},
-            Instr::type_ptr_off => unsafe {*((self.read_size_usize() + self.read_size_usize()) as *const u16)},
+            Ok(Instr::type_ptr) => {
+                let address = self.read_size_usize()?;
+                self.read_mem_u16(address)
+            },
+            Ok(Instr::type_ptr_off) => {
+                let address = self.read_size_usize()?;
+                let offset = self.read_size_usize()?;
+                let address = self.offset_address(address, offset)?;
+                self.read_mem_u16(address)
+            },
 
-            Instr::type_idr_ptr => unsafe {**(self.read_size_usize() as *const *const u16)},
-            Instr::type_idr_ptr_off => unsafe {
-                *((*(self.read_size_usize() as *const usize) + self.read_size_usize()) as *const u16)
+            Ok(Instr::type_idr_ptr) => {
+                let address = self.read_size_usize()?;
+                let address = self.read_mem_usize(address)?;
+                self.read_mem_u16(address)
+            },
+            Ok(Instr::type_idr_ptr_off) => {
+                let address = self.read_size_usize()?;
+                let offset = self.read_size_usize()?;
+                let address = self.read_mem_usize(address)?;
+                let address = self.offset_address(address, offset)?;
+                self.read_mem_u16(address)
             },
 
-            instr => panic!("Invalid type {:?} given, trying to read 16 bits", instr),
+            _ => Err(self.trap(TrapKind::InvalidType(kind))),
         }
     }
     #[inline]
-    pub fn read_u32(&mut self) -> u32 {
-        match Self::to_instr(self.next_u8()) {
-            Instr::type_int => self.read_size_32(),
+    pub fn read_u32(&mut self) -> Result<u32, Trap> {
+        let kind = self.next_u8()?;
+        match Instr::try_from(kind) {
+            Ok(Instr::type_int) => self.read_size_32(),
 
-            Instr::type_ptr => unsafe {*(self.read_size_usize() as *const u32)},
-            Instr::type_ptr_off => unsafe {*((self.read_size_usize() + self.read_size_usize()) as *const u32)},
+            Ok(Instr::type_ptr) => {
+                let address = self.read_size_usize()?;
+                self.read_mem_u32(address)
+            },
+            Ok(Instr::type_ptr_off) => {
+                let address = self.read_size_usize()?;
+                let offset = self.read_size_usize()?;
+                let address = self.offset_address(address, offset)?;
+                self.read_mem_u32(address)
+            },
 
-            Instr::type_idr_ptr => unsafe {**(self.read_size_usize() as *const *const u32)},
-            Instr::type_idr_ptr_off => unsafe {
-                *((*(self.read_size_usize() as *const usize) + self.read_size_usize()) as *const u32)
+            Ok(Instr::type_idr_ptr) => {
+                let address = self.read_size_usize()?;
+                let address = self.read_mem_usize(address)?;
+                self.read_mem_u32(address)
+            },
+            Ok(Instr::type_idr_ptr_off) => {
+                let address = self.read_size_usize()?;
+                let offset = self.read_size_usize()?;
+                let address = self.read_mem_usize(address)?;
+                let address = self.offset_address(address, offset)?;
+                self.read_mem_u32(address)
             },
 
-            instr => panic!("Invalid type {:?} given, trying to read 32 bits", instr),
+            _ => Err(self.trap(TrapKind::InvalidType(kind))),
         }
     }
     #[inline]
-    pub fn read_u64(&mut self) -> u64 {
-        match Self::to_instr(self.next_u8()) {
-            Instr::type_int => self.read_size_64(),
+    pub fn read_u64(&mut self) -> Result<u64, Trap> {
+        let kind = self.next_u8()?;
+        match Instr::try_from(kind) {
+            Ok(Instr::type_int) => self.read_size_64(),
 
-            Instr::type_ptr => unsafe {*(self.read_size_usize() as *const u64)},
-            Instr::type_ptr_off => unsafe {*((self.read_size_usize() + self.read_size_usize()) as *const u64)},
+            Ok(Instr::type_ptr) => {
+                let address = self.read_size_usize()?;
+                self.read_mem_u64(address)
+            },
+            Ok(Instr::type_ptr_off) => {
+                let address = self.read_size_usize()?;
+                let offset = self.read_size_usize()?;
+                let address = self.offset_address(address, offset)?;
+                self.read_mem_u64(address)
+            },
 
-            Instr::type_idr_ptr => unsafe {**(self.read_size_usize() as *const *const u64)},
-            Instr::type_idr_ptr_off => unsafe {
-                *((*(self.read_size_usize() as *const usize) + self.read_size_usize()) as *const u64)
+            Ok(Instr::type_idr_ptr) => {
+                let address = self.read_size_usize()?;
+                let address = self.read_mem_usize(address)?;
+                self.read_mem_u64(address)
+            },
+            Ok(Instr::type_idr_ptr_off) => {
+                let address = self.read_size_usize()?;
+                let offset = self.read_size_usize()?;
+                let address = self.read_mem_usize(address)?;
+                let address = self.offset_address(address, offset)?;
+                self.read_mem_u64(address)
             },
 
-            instr => panic!("Invalid type {:?} given, trying to read 64 bits", instr),
+            _ => Err(self.trap(TrapKind::InvalidType(kind))),
+        }
+    }
+
+    // type_int immediates of signed pushes are zigzag encoded in varint mode,
+    // None if the operand is read as unsigned bits
+    #[inline]
+    fn read_zigzag(&mut self) -> Result<Option<i64>, Trap> {
+        if self.encoding != Encoding::Varint ||
+            self.program.get(self.program_counter) != Some(&(Instr::type_int as u8)) {
+            return Ok(None);
+        }
+        self.program_counter += 1;
+        Ok(Some(encoding::unzigzag(self.read_varint()?)))
+    }
+
+    #[inline]
+    pub fn read_i8(&mut self) -> Result<i8, Trap> {
+        match self.read_zigzag()? {
+            Some(value) => Ok(value as i8),
+            None => Ok(self.read_u8()? as i8),
         }
     }
+    #[inline]
+    pub fn read_i16(&mut self) -> Result<i16, Trap> {
+        match self.read_zigzag()? {
+            Some(value) => Ok(value as i16),
+            None => Ok(self.read_u16()? as i16),
+        }
+    }
+    #[inline]
+    pub fn read_i32(&mut self) -> Result<i32, Trap> {
+        match self.read_zigzag()? {
+            Some(value) => Ok(value as i32),
+            None => Ok(self.read_u32()? as i32),
+        }
+    }
+    #[inline]
+    pub fn read_i64(&mut self) -> Result<i64, Trap> {
+        match self.read_zigzag()? {
+            Some(value) => Ok(value),
+            None => Ok(self.read_u64()? as i64),
+        }
+    }
+
+    #[inline]
+    pub fn read_f32(&mut self) -> Result<f32, Trap> {Ok(f32::from_bits(self.read_u32()?))}
+    #[inline]
+    pub fn read_f64(&mut self) -> Result<f64, Trap> {Ok(f64::from_bits(self.read_u64()?))}
 
     #[cfg(target_pointer_width = "32")]
     #[inline]
-    pub fn read_usize(&mut self) -> usize {self.read_u32() as usize}
+    pub fn read_usize(&mut self) -> Result<usize, Trap> {Ok(self.read_u32()? as usize)}
     #[cfg(target_pointer_width = "64")]
     #[inline]
-    pub fn read_usize(&mut self) -> usize {self.read_u64() as usize}
+    pub fn read_usize(&mut self) -> Result<usize, Trap> {Ok(self.read_u64()? as usize)}
+
+    // stops the process, vm::run returns Status::Halted
+    #[inline]
+    pub fn halt(&mut self) {
+        self.exit_code = Some(0);
+        self.state = State::Terminated;
+    }
+    #[inline]
+    pub fn halt_with(&mut self) -> Result<(), Trap> {
+        let code = self.pop_u32()?;
+        self.exit_code = Some(code);
+        self.state = State::Terminated;
+        Ok(())
+    }
+    // returns control to the caller of vm::run_for, the process stays Running
+    #[inline]
+    pub fn pause(&mut self) {self.paused = true;}
+    // blocks on the popped event until the process is woken
+    #[inline]
+    pub fn wait(&mut self) -> Result<(), Trap> {
+        let event = self.pop_u64()?;
+        self.event = Some(event);
+        self.state = State::Waiting;
+        Ok(())
+    }
 
+    // moves the top args bytes onto the stack of a child starting at the
+    // target, see take_spawned
     #[inline]
-    pub fn halt(&mut self) {self.state = State::Waiting;}
+    pub fn spawn(&mut self) -> Result<(), Trap> {
+        let index = self.read_usize()?;
+        let args = self.read_usize()?;
+        if args > self.stack.len() {
+            return Err(self.trap(TrapKind::StackUnderflow));
+        }
+
+        let mut stack = Vector::with_capacity(args);
+        for byte in self.stack[self.stack.len() - args..].iter() {
+            stack.push(*byte);
+        }
+        for _ in 0..args {
+            self.stack.pop();
+        }
+        self.spawned = Some((index, stack));
+        Ok(())
+    }
 
     #[inline]
-    pub fn jmp(&mut self) {
-        let index = self.read_usize();
+    pub fn jmp(&mut self) -> Result<(), Trap> {
+        let index = self.read_usize()?;
         self.program_counter = index;
+        Ok(())
     }
     #[inline]
-    pub fn if_jmp(&mut self) {
-        let value = self.pop_u8();
+    pub fn if_jmp(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u8()?;
 
         if value == 0 {
-            self.read_usize(); // skip target value
+            self.read_usize()?; // skip target value
         } else {
-            let index = self.read_usize();
+            let index = self.read_usize()?;
+            self.program_counter = index;
+        }
+        Ok(())
+    }
+
+    // jumps to the target operand when taken, skips it otherwise
+    #[inline]
+    fn branch(&mut self, taken: bool) -> Result<(), Trap> {
+        let index = self.read_usize()?;
+        if taken {
             self.program_counter = index;
         }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn br_eq_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.branch(a == b)
+    }
+    #[inline]
+    pub fn br_eq_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.branch(a == b)
+    }
+    #[inline]
+    pub fn br_eq_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.branch(a == b)
+    }
+    #[inline]
+    pub fn br_eq_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.branch(a == b)
+    }
+    #[inline]
+    pub fn br_eq_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
+        self.branch(a == b)
+    }
+    #[inline]
+    pub fn br_eq_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
+        self.branch(a == b)
+    }
+
+    #[inline]
+    pub fn br_lt_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.branch(a < b)
+    }
+    #[inline]
+    pub fn br_lt_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.branch(a < b)
+    }
+    #[inline]
+    pub fn br_lt_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.branch(a < b)
+    }
+    #[inline]
+    pub fn br_lt_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.branch(a < b)
+    }
+    #[inline]
+    pub fn br_lt_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        self.branch(a < b)
+    }
+    #[inline]
+    pub fn br_lt_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        self.branch(a < b)
+    }
+    #[inline]
+    pub fn br_lt_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        self.branch(a < b)
+    }
+    #[inline]
+    pub fn br_lt_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        self.branch(a < b)
+    }
+    #[inline]
+    pub fn br_lt_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
+        self.branch(a < b)
+    }
+    #[inline]
+    pub fn br_lt_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
+        self.branch(a < b)
     }
 
     #[inline]
-    pub fn call(&mut self) {
-        let index = self.read_usize();
-        self.function_stack.push(self.program_counter);
+    pub fn br_zero_u8(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u8()?;
+        self.branch(value == 0)
+    }
+    #[inline]
+    pub fn br_zero_u16(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u16()?;
+        self.branch(value == 0)
+    }
+    #[inline]
+    pub fn br_zero_u32(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u32()?;
+        self.branch(value == 0)
+    }
+    #[inline]
+    pub fn br_zero_u64(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u64()?;
+        self.branch(value == 0)
+    }
+    #[inline]
+    pub fn br_zero_f32(&mut self) -> Result<(), Trap> {
+        let value = self.pop_f32()?;
+        self.branch(value == 0.0)
+    }
+    #[inline]
+    pub fn br_zero_f64(&mut self) -> Result<(), Trap> {
+        let value = self.pop_f64()?;
+        self.branch(value == 0.0)
+    }
+
+    #[inline]
+    pub fn br_nonzero_u8(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u8()?;
+        self.branch(value != 0)
+    }
+    #[inline]
+    pub fn br_nonzero_u16(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u16()?;
+        self.branch(value != 0)
+    }
+    #[inline]
+    pub fn br_nonzero_u32(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u32()?;
+        self.branch(value != 0)
+    }
+    #[inline]
+    pub fn br_nonzero_u64(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u64()?;
+        self.branch(value != 0)
+    }
+    #[inline]
+    pub fn br_nonzero_f32(&mut self) -> Result<(), Trap> {
+        let value = self.pop_f32()?;
+        self.branch(value != 0.0)
+    }
+    #[inline]
+    pub fn br_nonzero_f64(&mut self) -> Result<(), Trap> {
+        let value = self.pop_f64()?;
+        self.branch(value != 0.0)
+    }
+
+    // continues at index in a new frame holding the top args bytes,
+    // returning to the next instruction on ret
+    #[inline]
+    fn call_address(&mut self, index: usize, args: usize) -> Result<(), Trap> {
+        let len = self.stack.len();
+        if args > len {
+            return Err(self.trap(TrapKind::StackUnderflow));
+        }
+
+        self.function_stack.push(Frame {
+            return_address: self.program_counter,
+            frame_pointer: self.frame_pointer,
+            locals_end: self.locals_end,
+        });
+        self.frame_pointer = len - args;
+        self.locals_end = len;
         self.program_counter = index;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn call(&mut self) -> Result<(), Trap> {
+        let index = self.read_usize()?;
+        self.call_address(index, 0)
+    }
+    #[inline]
+    pub fn call_args(&mut self) -> Result<(), Trap> {
+        let index = self.read_usize()?;
+        let args = self.read_usize()?;
+        self.call_address(index, args)
+    }
+    // target of a relative operand, offsets count from the start of the
+    // instruction so the code can be moved as a whole
+    #[inline]
+    fn read_relative(&mut self) -> Result<usize, Trap> {
+        let offset = self.read_i32()?;
+        Ok(self.instr_counter.wrapping_add(offset as isize as usize))
+    }
+
+    #[inline]
+    pub fn jmp_rel(&mut self) -> Result<(), Trap> {
+        let index = self.read_relative()?;
+        self.program_counter = index;
+        Ok(())
+    }
+    #[inline]
+    pub fn if_jmp_rel(&mut self) -> Result<(), Trap> {
+        let value = self.pop_u8()?;
+        let index = self.read_relative()?;
+
+        if value != 0 {
+            self.program_counter = index;
+        }
+        Ok(())
+    }
+    #[inline]
+    pub fn call_rel(&mut self) -> Result<(), Trap> {
+        let index = self.read_relative()?;
+        self.call_address(index, 0)
+    }
+
+    // target popped from the stack, checked against the entry points
+    #[inline]
+    fn pop_target(&mut self) -> Result<usize, Trap> {
+        let index = self.pop_usize()?;
+
+        match self.entry_points {
+            Some(ref entry_points) if entry_points.binary_search(&index).is_err() => {
+                Err(self.trap(TrapKind::InvalidTarget(index)))
+            },
+            _ => Ok(index),
+        }
+    }
+
+    #[inline]
+    pub fn jmp_indirect(&mut self) -> Result<(), Trap> {
+        let index = self.pop_target()?;
+        self.program_counter = index;
+        Ok(())
+    }
+    #[inline]
+    pub fn call_indirect(&mut self) -> Result<(), Trap> {
+        let index = self.pop_target()?;
+        self.call_address(index, 0)
+    }
+
+    // drops the frame, what was pushed after the locals takes its place
+    #[inline]
+    pub fn ret(&mut self) -> Result<(), Trap> {
+        let frame = match self.function_stack.pop() {
+            Some(frame) => frame,
+            None => return Err(self.trap(TrapKind::CallStackUnderflow)),
+        };
+
+        // a frame without arguments or locals is left as it is
+        if self.locals_end != self.frame_pointer {
+            let len = self.stack.len();
+            if len < self.locals_end {
+                return Err(self.trap(TrapKind::StackUnderflow));
+            }
+            self.stack.copy_within(self.locals_end..len, self.frame_pointer);
+            for _ in self.frame_pointer..self.locals_end {
+                self.stack.pop();
+            }
+        }
+
+        self.program_counter = frame.return_address;
+        self.frame_pointer = frame.frame_pointer;
+        self.locals_end = frame.locals_end;
+        Ok(())
+    }
+
+    // reserves zeroed locals after the frame's arguments
+    #[inline]
+    pub fn enter(&mut self) -> Result<(), Trap> {
+        let locals = self.read_usize()?;
+        for _ in 0..locals {
+            self.stack.push(0);
+        }
+        self.locals_end = self.stack.len();
+        Ok(())
+    }
+
+    // stack index of size bytes at offset in the frame
+    #[inline]
+    fn local_index(&self, offset: usize, size: usize) -> Result<usize, Trap> {
+        let end = cmp::min(self.locals_end, self.stack.len());
+
+        match self.frame_pointer.checked_add(offset) {
+            Some(index) if index <= end && size <= end - index => Ok(index),
+            _ => Err(self.trap(TrapKind::InvalidLocal(offset))),
+        }
+    }
+    #[inline]
+    fn local_get(&mut self, size: usize) -> Result<(), Trap> {
+        let offset = self.read_usize()?;
+        let index = self.local_index(offset, size)?;
+
+        for i in index..index + size {
+            let byte = self.stack[i];
+            self.stack.push(byte);
+        }
+        Ok(())
+    }
+    #[inline]
+    fn local_set(&mut self, size: usize) -> Result<(), Trap> {
+        let offset = self.read_usize()?;
+        let mut bytes = [0u8; 8];
+        for i in (0..size).rev() {
+            bytes[i] = self.pop_u8()?;
+        }
+
+        let index = self.local_index(offset, size)?;
+        self.stack[index..index + size].copy_from_slice(&bytes[..size]);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn local_get_u8(&mut self) -> Result<(), Trap> {self.local_get(1)}
+    #[inline]
+    pub fn local_get_u16(&mut self) -> Result<(), Trap> {self.local_get(2)}
+    #[inline]
+    pub fn local_get_u32(&mut self) -> Result<(), Trap> {self.local_get(4)}
+    #[inline]
+    pub fn local_get_u64(&mut self) -> Result<(), Trap> {self.local_get(8)}
+
+    #[inline]
+    pub fn local_set_u8(&mut self) -> Result<(), Trap> {self.local_set(1)}
+    #[inline]
+    pub fn local_set_u16(&mut self) -> Result<(), Trap> {self.local_set(2)}
+    #[inline]
+    pub fn local_set_u32(&mut self) -> Result<(), Trap> {self.local_set(4)}
+    #[inline]
+    pub fn local_set_u64(&mut self) -> Result<(), Trap> {self.local_set(8)}
+
+    #[inline]
+    pub fn pop_u8(&mut self) -> Result<u8, Trap> {
+        match self.stack.pop() {
+            Some(value) => Ok(value),
+            None => Err(self.trap(TrapKind::StackUnderflow)),
+        }
+    }
+    #[inline]
+    pub fn pop_u16(&mut self) -> Result<u16, Trap> {
+        let b0 = self.pop_u8()?;
+        let b1 = self.pop_u8()?;
+        Ok(Self::to_u16(b1, b0))
+    }
+    #[inline]
+    pub fn pop_u32(&mut self) -> Result<u32, Trap> {
+        let b0 = self.pop_u8()?;
+        let b1 = self.pop_u8()?;
+        let b2 = self.pop_u8()?;
+        let b3 = self.pop_u8()?;
+        Ok(Self::to_u32(b3, b2, b1, b0))
+    }
+    #[inline]
+    pub fn pop_u64(&mut self) -> Result<u64, Trap> {
+        let b0 = self.pop_u8()?;
+        let b1 = self.pop_u8()?;
+        let b2 = self.pop_u8()?;
+        let b3 = self.pop_u8()?;
+        let b4 = self.pop_u8()?;
+        let b5 = self.pop_u8()?;
+        let b6 = self.pop_u8()?;
+        let b7 = self.pop_u8()?;
+        Ok(Self::to_u64(b7, b6, b5, b4, b3, b2, b1, b0))
+    }
+
+    #[inline]
+    pub fn pop_f32(&mut self) -> Result<f32, Trap> {Ok(f32::from_bits(self.pop_u32()?))}
+    #[inline]
+    pub fn pop_f64(&mut self) -> Result<f64, Trap> {Ok(f64::from_bits(self.pop_u64()?))}
+
+    #[cfg(target_pointer_width = "32")]
+    #[inline]
+    pub fn pop_usize(&mut self) -> Result<usize, Trap> {Ok(self.pop_u32()? as usize)}
+    #[cfg(target_pointer_width = "64")]
+    #[inline]
+    pub fn pop_usize(&mut self) -> Result<usize, Trap> {Ok(self.pop_u64()? as usize)}
+
+    #[inline]
+    pub fn peek_stack(&self, offset: usize) -> Result<u8, Trap> {
+        let len = self.stack.len();
+
+        if offset < len {
+            Ok(self.stack[len - offset - 1])
+        } else {
+            Err(self.trap(TrapKind::StackUnderflow))
+        }
+    }
+    #[inline]
+    pub fn peek_u8(&self, offset: usize) -> Result<u8, Trap> {
+        self.peek_stack(offset)
+    }
+    #[inline]
+    pub fn peek_u16(&self, offset: usize) -> Result<u16, Trap> {
+        let b0 = self.peek_stack(offset + 0)?;
+        let b1 = self.peek_stack(offset + 1)?;
+        Ok(Self::to_u16(b1, b0))
+    }
+    #[inline]
+    pub fn peek_u32(&self, offset: usize) -> Result<u32, Trap> {
+        let b0 = self.peek_stack(offset + 0)?;
+        let b1 = self.peek_stack(offset + 1)?;
+        let b2 = self.peek_stack(offset + 2)?;
+        let b3 = self.peek_stack(offset + 3)?;
+        Ok(Self::to_u32(b3, b2, b1, b0))
+    }
+    #[inline]
+    pub fn peek_u64(&self, offset: usize) -> Result<u64, Trap> {
+        let b0 = self.peek_stack(offset + 0)?;
+        let b1 = self.peek_stack(offset + 1)?;
+        let b2 = self.peek_stack(offset + 2)?;
+        let b3 = self.peek_stack(offset + 3)?;
+        let b4 = self.peek_stack(offset + 4)?;
+        let b5 = self.peek_stack(offset + 5)?;
+        let b6 = self.peek_stack(offset + 6)?;
+        let b7 = self.peek_stack(offset + 7)?;
+        Ok(Self::to_u64(b7, b6, b5, b4, b3, b2, b1, b0))
+    }
+
+    #[inline]
+    pub fn peek_f32(&self, offset: usize) -> Result<f32, Trap> {Ok(f32::from_bits(self.peek_u32(offset)?))}
+    #[inline]
+    pub fn peek_f64(&self, offset: usize) -> Result<f64, Trap> {Ok(f64::from_bits(self.peek_u64(offset)?))}
+
+    #[cfg(target_pointer_width = "32")]
+    #[inline]
+    pub fn peek_usize(&mut self, offset: usize) -> Result<usize, Trap> {Ok(self.peek_u32(offset)? as usize)}
+    #[cfg(target_pointer_width = "64")]
+    #[inline]
+    pub fn peek_usize(&mut self, offset: usize) -> Result<usize, Trap> {Ok(self.peek_u64(offset)? as usize)}
+
+    #[inline]
+    pub fn push_u8(&mut self, value: u8) {
+        let ref mut stack = self.stack;
+        stack.push(value);
+    }
+    #[inline]
+    pub fn push_u16(&mut self, value: u16) {
+        let ref mut stack = self.stack;
+        stack.push((value >> 8) as u8);
+        stack.push(value as u8);
+    }
+    #[inline]
+    pub fn push_u32(&mut self, value: u32) {
+        let ref mut stack = self.stack;
+        stack.push((value >> 24) as u8);
+        stack.push((value >> 16) as u8);
+        stack.push((value >> 8) as u8);
+        stack.push(value as u8);
+    }
+    #[inline]
+    pub fn push_u64(&mut self, value: u64) {
+        let ref mut stack = self.stack;
+        stack.push((value >> 56) as u8);
+        stack.push((value >> 48) as u8);
+        stack.push((value >> 40) as u8);
+        stack.push((value >> 32) as u8);
+        stack.push((value >> 24) as u8);
+        stack.push((value >> 16) as u8);
+        stack.push((value >> 8) as u8);
+        stack.push(value as u8);
+    }
+
+    #[inline]
+    pub fn push_f32(&mut self, value: f32) {self.push_u32(value.to_bits());}
+    #[inline]
+    pub fn push_f64(&mut self, value: f64) {self.push_u64(value.to_bits());}
+
+    #[cfg(target_pointer_width = "32")]
+    #[inline]
+    pub fn push_usize(&mut self, value: usize) {self.push_u32(value as u32);}
+    #[cfg(target_pointer_width = "64")]
+    #[inline]
+    pub fn push_usize(&mut self, value: usize) {self.push_u64(value as u64);}
+
+    #[inline]
+    pub fn copy_u8(&mut self) -> Result<(), Trap> {
+        let value = self.peek_u8(0)?;
+        let ref mut stack = self.stack;
+        stack.push(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn copy_u16(&mut self) -> Result<(), Trap> {
+        let value = self.peek_u16(0)?;
+        let ref mut stack = self.stack;
+        stack.push((value >> 8) as u8);
+        stack.push(value as u8);
+        Ok(())
+    }
+    #[inline]
+    pub fn copy_u32(&mut self) -> Result<(), Trap> {
+        let value = self.peek_u32(0)?;
+        let ref mut stack = self.stack;
+        stack.push((value >> 24) as u8);
+        stack.push((value >> 16) as u8);
+        stack.push((value >> 8) as u8);
+        stack.push(value as u8);
+        Ok(())
+    }
+    #[inline]
+    pub fn copy_u64(&mut self) -> Result<(), Trap> {
+        let value = self.peek_u64(0)?;
+        let ref mut stack = self.stack;
+        stack.push((value >> 56) as u8);
+        stack.push((value >> 48) as u8);
+        stack.push((value >> 40) as u8);
+        stack.push((value >> 32) as u8);
+        stack.push((value >> 24) as u8);
+        stack.push((value >> 16) as u8);
+        stack.push((value >> 8) as u8);
+        stack.push(value as u8);
+        Ok(())
+    }
+
+    // bytes from the top of the stack to the start of the value depth
+    // values of size bytes below the top
+    #[inline]
+    fn value_depth(&self, size: usize, depth: usize) -> Result<usize, Trap> {
+        match depth.checked_add(1).and_then(|values| values.checked_mul(size)) {
+            Some(bytes) => {
+                self.peek_stack(bytes - 1)?;
+                Ok(bytes)
+            }
+            None => Err(self.trap(TrapKind::StackUnderflow)),
+        }
+    }
+    // copies the value depth values below the top to the top
+    #[inline]
+    fn pick(&mut self, size: usize, depth: usize) -> Result<(), Trap> {
+        let bytes = self.value_depth(size, depth)?;
+        let start = self.stack.len() - bytes;
+        let mut value = [0u8; 8];
+        value[..size].copy_from_slice(&self.stack[start..start + size]);
+
+        for byte in value[..size].iter() {
+            self.stack.push(*byte);
+        }
+        Ok(())
+    }
+    // moves the value depth values below the top to the top, the values
+    // above it shift down
+    #[inline]
+    fn roll(&mut self, size: usize, depth: usize) -> Result<(), Trap> {
+        let bytes = self.value_depth(size, depth)?;
+        let len = self.stack.len();
+        self.stack[len - bytes..].rotate_left(size);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn swap_u8(&mut self) -> Result<(), Trap> {self.roll(1, 1)}
+    #[inline]
+    pub fn swap_u16(&mut self) -> Result<(), Trap> {self.roll(2, 1)}
+    #[inline]
+    pub fn swap_u32(&mut self) -> Result<(), Trap> {self.roll(4, 1)}
+    #[inline]
+    pub fn swap_u64(&mut self) -> Result<(), Trap> {self.roll(8, 1)}
+
+    #[inline]
+    pub fn over_u8(&mut self) -> Result<(), Trap> {self.pick(1, 1)}
+    #[inline]
+    pub fn over_u16(&mut self) -> Result<(), Trap> {self.pick(2, 1)}
+    #[inline]
+    pub fn over_u32(&mut self) -> Result<(), Trap> {self.pick(4, 1)}
+    #[inline]
+    pub fn over_u64(&mut self) -> Result<(), Trap> {self.pick(8, 1)}
+
+    #[inline]
+    pub fn rot_u8(&mut self) -> Result<(), Trap> {self.roll(1, 2)}
+    #[inline]
+    pub fn rot_u16(&mut self) -> Result<(), Trap> {self.roll(2, 2)}
+    #[inline]
+    pub fn rot_u32(&mut self) -> Result<(), Trap> {self.roll(4, 2)}
+    #[inline]
+    pub fn rot_u64(&mut self) -> Result<(), Trap> {self.roll(8, 2)}
+
+    #[inline]
+    pub fn pick_u8(&mut self) -> Result<(), Trap> {
+        let depth = self.read_usize()?;
+        self.pick(1, depth)
+    }
+    #[inline]
+    pub fn pick_u16(&mut self) -> Result<(), Trap> {
+        let depth = self.read_usize()?;
+        self.pick(2, depth)
+    }
+    #[inline]
+    pub fn pick_u32(&mut self) -> Result<(), Trap> {
+        let depth = self.read_usize()?;
+        self.pick(4, depth)
+    }
+    #[inline]
+    pub fn pick_u64(&mut self) -> Result<(), Trap> {
+        let depth = self.read_usize()?;
+        self.pick(8, depth)
+    }
+    #[inline]
+    pub fn roll_u8(&mut self) -> Result<(), Trap> {
+        let depth = self.read_usize()?;
+        self.roll(1, depth)
+    }
+    #[inline]
+    pub fn roll_u16(&mut self) -> Result<(), Trap> {
+        let depth = self.read_usize()?;
+        self.roll(2, depth)
+    }
+    #[inline]
+    pub fn roll_u32(&mut self) -> Result<(), Trap> {
+        let depth = self.read_usize()?;
+        self.roll(4, depth)
+    }
+    #[inline]
+    pub fn roll_u64(&mut self) -> Result<(), Trap> {
+        let depth = self.read_usize()?;
+        self.roll(8, depth)
+    }
+
+    #[inline]
+    pub fn drop_bytes(&mut self) -> Result<(), Trap> {
+        let count = self.read_usize()?;
+        if count > self.stack.len() {
+            return Err(self.trap(TrapKind::StackUnderflow));
+        }
+
+        for _ in 0..count {
+            self.stack.pop();
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn load_u8(&mut self) -> Result<(), Trap> {
+        let address = self.pop_usize()?;
+        let value = self.read_mem_u8(address)?;
+        self.push_u8(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn load_u16(&mut self) -> Result<(), Trap> {
+        let address = self.pop_usize()?;
+        let value = self.read_mem_u16(address)?;
+        self.push_u16(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn load_u32(&mut self) -> Result<(), Trap> {
+        let address = self.pop_usize()?;
+        let value = self.read_mem_u32(address)?;
+        self.push_u32(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn load_u64(&mut self) -> Result<(), Trap> {
+        let address = self.pop_usize()?;
+        let value = self.read_mem_u64(address)?;
+        self.push_u64(value);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn save_u8(&mut self) -> Result<(), Trap> {
+        let address = self.pop_usize()?;
+        let value = self.pop_u8()?;
+        self.write_mem_u8(address, value)
+    }
+    #[inline]
+    pub fn save_u16(&mut self) -> Result<(), Trap> {
+        let address = self.pop_usize()?;
+        let value = self.pop_u16()?;
+        self.write_mem_u16(address, value)
+    }
+    #[inline]
+    pub fn save_u32(&mut self) -> Result<(), Trap> {
+        let address = self.pop_usize()?;
+        let value = self.pop_u32()?;
+        self.write_mem_u32(address, value)
+    }
+    #[inline]
+    pub fn save_u64(&mut self) -> Result<(), Trap> {
+        let address = self.pop_usize()?;
+        let value = self.pop_u64()?;
+        self.write_mem_u64(address, value)
+    }
+
+    /*
+        TODO use concat_idents when working
+        https://github.com/rust-lang/rust/issues/29599
+    */
+    #[inline]
+    pub fn add_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.push_u8(a.wrapping_add(b));
+        Ok(())
+    }
+    #[inline]
+    pub fn add_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.push_u16(a.wrapping_add(b));
+        Ok(())
+    }
+    #[inline]
+    pub fn add_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.push_u32(a.wrapping_add(b));
+        Ok(())
+    }
+    #[inline]
+    pub fn add_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.push_u64(a.wrapping_add(b));
+        Ok(())
+    }
+
+    #[inline]
+    pub fn add_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.wrapping_add(b) as u8);
+        Ok(())
+    }
+    #[inline]
+    pub fn add_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.wrapping_add(b) as u16);
+        Ok(())
+    }
+    #[inline]
+    pub fn add_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.wrapping_add(b) as u32);
+        Ok(())
+    }
+    #[inline]
+    pub fn add_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.wrapping_add(b) as u64);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn add_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
+        self.push_f32(a + b);
+        Ok(())
     }
     #[inline]
-    pub fn ret(&mut self) {
-        let index = self.function_stack.pop().expect("Unexpected end of function stack");
-        self.program_counter = index;
+    pub fn add_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
+        self.push_f64(a + b);
+        Ok(())
     }
 
     #[inline]
-    pub fn pop_u8(&mut self) -> u8 {
-        self.stack.pop().expect("Unexpected end of stack")
+    pub fn add_u8_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        match a.checked_add(b) {
+            Some(value) => self.push_u8(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn pop_u16(&mut self) -> u16 {
-        let b0 = self.pop_u8();
-        let b1 = self.pop_u8();
-        Self::to_u16(b1, b0)
+    pub fn add_u16_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        match a.checked_add(b) {
+            Some(value) => self.push_u16(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn pop_u32(&mut self) -> u32 {
-        let b0 = self.pop_u8();
-        let b1 = self.pop_u8();
-        let b2 = self.pop_u8();
-        let b3 = self.pop_u8();
-        Self::to_u32(b3, b2, b1, b0)
+    pub fn add_u32_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        match a.checked_add(b) {
+            Some(value) => self.push_u32(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn pop_u64(&mut self) -> u64 {
-        let b0 = self.pop_u8();
-        let b1 = self.pop_u8();
-        let b2 = self.pop_u8();
-        let b3 = self.pop_u8();
-        let b4 = self.pop_u8();
-        let b5 = self.pop_u8();
-        let b6 = self.pop_u8();
-        let b7 = self.pop_u8();
-        Self::to_u64(b7, b6, b5, b4, b3, b2, b1, b0)
+    pub fn add_u64_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        match a.checked_add(b) {
+            Some(value) => self.push_u64(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
 
-    #[cfg(target_pointer_width = "32")]
-    #[inline]
-    pub fn pop_usize(&mut self) -> usize {self.pop_u32() as usize}
-    #[cfg(target_pointer_width = "64")]
     #[inline]
-    pub fn pop_usize(&mut self) -> usize {self.pop_u64() as usize}
-
+    pub fn add_i8_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        match a.checked_add(b) {
+            Some(value) => self.push_u8(value as u8),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
+    }
     #[inline]
-    pub fn peek_stack(&self, offset: usize) -> u8 {
-        let len = self.stack.len();
-        self.stack[len - offset - 1]
+    pub fn add_i16_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        match a.checked_add(b) {
+            Some(value) => self.push_u16(value as u16),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn peek_u8(&self, offset: usize) -> u8 {
-        self.peek_stack(offset)
+    pub fn add_i32_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        match a.checked_add(b) {
+            Some(value) => self.push_u32(value as u32),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn peek_u16(&self, offset: usize) -> u16 {
-        let b0 = self.peek_stack(offset + 0);
-        let b1 = self.peek_stack(offset + 1);
-        Self::to_u16(b1, b0)
+    pub fn add_i64_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        match a.checked_add(b) {
+            Some(value) => self.push_u64(value as u64),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
+
     #[inline]
-    pub fn peek_u32(&self, offset: usize) -> u32 {
-        let b0 = self.peek_stack(offset + 0);
-        let b1 = self.peek_stack(offset + 1);
-        let b2 = self.peek_stack(offset + 2);
-        let b3 = self.peek_stack(offset + 3);
-        Self::to_u32(b3, b2, b1, b0)
+    pub fn add_u8_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.push_u8(a.saturating_add(b));
+        Ok(())
     }
     #[inline]
-    pub fn peek_u64(&self, offset: usize) -> u64 {
-        let b0 = self.peek_stack(offset + 0);
-        let b1 = self.peek_stack(offset + 1);
-        let b2 = self.peek_stack(offset + 2);
-        let b3 = self.peek_stack(offset + 3);
-        let b4 = self.peek_stack(offset + 4);
-        let b5 = self.peek_stack(offset + 5);
-        let b6 = self.peek_stack(offset + 6);
-        let b7 = self.peek_stack(offset + 7);
-        Self::to_u64(b7, b6, b5, b4, b3, b2, b1, b0)
+    pub fn add_u16_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.push_u16(a.saturating_add(b));
+        Ok(())
     }
-
-    #[cfg(target_pointer_width = "32")]
     #[inline]
-    pub fn peek_usize(&mut self, offset: usize) -> usize {self.peek_u32(offset) as usize}
-    #[cfg(target_pointer_width = "64")]
+    pub fn add_u32_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.push_u32(a.saturating_add(b));
+        Ok(())
+    }
     #[inline]
-    pub fn peek_usize(&mut self, offset: usize) -> usize {self.peek_u64(offset) as usize}
+    pub fn add_u64_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.push_u64(a.saturating_add(b));
+        Ok(())
+    }
 
     #[inline]
-    pub fn push_u8(&mut self, value: u8) {
-        let ref mut stack = self.stack;
-        stack.push(value);
+    pub fn add_i8_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.saturating_add(b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn push_u16(&mut self, value: u16) {
-        let ref mut stack = self.stack;
-        stack.push((value >> 8) as u8);
-        stack.push(value as u8);
+    pub fn add_i16_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.saturating_add(b) as u16);
+        Ok(())
     }
     #[inline]
-    pub fn push_u32(&mut self, value: u32) {
-        let ref mut stack = self.stack;
-        stack.push((value >> 24) as u8);
-        stack.push((value >> 16) as u8);
-        stack.push((value >> 8) as u8);
-        stack.push(value as u8);
+    pub fn add_i32_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.saturating_add(b) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn push_u64(&mut self, value: u64) {
-        let ref mut stack = self.stack;
-        stack.push((value >> 56) as u8);
-        stack.push((value >> 48) as u8);
-        stack.push((value >> 40) as u8);
-        stack.push((value >> 32) as u8);
-        stack.push((value >> 24) as u8);
-        stack.push((value >> 16) as u8);
-        stack.push((value >> 8) as u8);
-        stack.push(value as u8);
+    pub fn add_i64_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.saturating_add(b) as u64);
+        Ok(())
     }
 
-    #[cfg(target_pointer_width = "32")]
-    #[inline]
-    pub fn push_usize(&mut self, value: usize) {self.push_u32(value as u32);}
-    #[cfg(target_pointer_width = "64")]
-    #[inline]
-    pub fn push_usize(&mut self, value: usize) {self.push_u64(value as u64);}
 
     #[inline]
-    pub fn copy_u8(&mut self) {
-        let value = self.peek_u8(0);
-        let ref mut stack = self.stack;
-        stack.push(value);
+    pub fn sub_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.push_u8(a.wrapping_sub(b));
+        Ok(())
     }
     #[inline]
-    pub fn copy_u16(&mut self) {
-        let value = self.peek_u16(0);
-        let ref mut stack = self.stack;
-        stack.push((value >> 8) as u8);
-        stack.push(value as u8);
+    pub fn sub_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.push_u16(a.wrapping_sub(b));
+        Ok(())
     }
     #[inline]
-    pub fn copy_u32(&mut self) {
-        let value = self.peek_u32(0);
-        let ref mut stack = self.stack;
-        stack.push((value >> 24) as u8);
-        stack.push((value >> 16) as u8);
-        stack.push((value >> 8) as u8);
-        stack.push(value as u8);
+    pub fn sub_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.push_u32(a.wrapping_sub(b));
+        Ok(())
     }
     #[inline]
-    pub fn copy_u64(&mut self) {
-        let value = self.peek_u64(0);
-        let ref mut stack = self.stack;
-        stack.push((value >> 56) as u8);
-        stack.push((value >> 48) as u8);
-        stack.push((value >> 40) as u8);
-        stack.push((value >> 32) as u8);
-        stack.push((value >> 24) as u8);
-        stack.push((value >> 16) as u8);
-        stack.push((value >> 8) as u8);
-        stack.push(value as u8);
+    pub fn sub_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.push_u64(a.wrapping_sub(b));
+        Ok(())
     }
 
     #[inline]
-    pub fn load_u8(&mut self) {
-        let value = unsafe {*(self.pop_usize() as *const u8)};
-        self.stack.push(value);
+    pub fn sub_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.wrapping_sub(b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn load_u16(&mut self) {
-        let value = unsafe {*(self.pop_usize() as *const u16)};
-        let ref mut stack = self.stack;
-        stack.push((value >> 8) as u8);
-        stack.push(value as u8);
+    pub fn sub_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.wrapping_sub(b) as u16);
+        Ok(())
     }
     #[inline]
-    pub fn load_u32(&mut self) {
-        let value = unsafe {*(self.pop_usize() as *const u32)};
-        let ref mut stack = self.stack;
-        stack.push((value >> 24) as u8);
-        stack.push((value >> 16) as u8);
-        stack.push((value >> 8) as u8);
-        stack.push(value as u8);
+    pub fn sub_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.wrapping_sub(b) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn load_u64(&mut self) {
-        let value = unsafe {*(self.pop_usize() as *const u64)};
-        let ref mut stack = self.stack;
-        stack.push((value >> 56) as u8);
-        stack.push((value >> 48) as u8);
-        stack.push((value >> 40) as u8);
-        stack.push((value >> 32) as u8);
-        stack.push((value >> 24) as u8);
-        stack.push((value >> 16) as u8);
-        stack.push((value >> 8) as u8);
-        stack.push(value as u8);
+    pub fn sub_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.wrapping_sub(b) as u64);
+        Ok(())
     }
 
     #[inline]
-    pub fn save_u8(&mut self) {
-        let mut address = self.pop_usize() as *mut u8;
-        let value = self.pop_u8();
-        unsafe {*address = value};
-    }
-    #[inline]
-    pub fn save_u16(&mut self) {
-        let mut address = self.pop_usize() as *mut u16;
-        let value = self.pop_u16();
-        unsafe {*address = value};
-    }
-    #[inline]
-    pub fn save_u32(&mut self) {
-        let mut address = self.pop_usize() as *mut u32;
-        let value = self.pop_u32();
-        unsafe {*address = value};
+    pub fn sub_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
+        self.push_f32(a - b);
+        Ok(())
     }
     #[inline]
-    pub fn save_u64(&mut self) {
-        let mut address = self.pop_usize() as *mut u64;
-        let value = self.pop_u64();
-        unsafe {*address = value};
+    pub fn sub_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
+        self.push_f64(a - b);
+        Ok(())
     }
 
-    /*
-        TODO use concat_idents when working
-        https://github.com/rust-lang/rust/issues/29599
-    */
     #[inline]
-    pub fn add_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
-        self.push_u8(a + b);
+    pub fn sub_u8_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        match a.checked_sub(b) {
+            Some(value) => self.push_u8(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn add_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
-        self.push_u16(a + b);
+    pub fn sub_u16_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        match a.checked_sub(b) {
+            Some(value) => self.push_u16(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn add_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
-        self.push_u32(a + b);
+    pub fn sub_u32_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        match a.checked_sub(b) {
+            Some(value) => self.push_u32(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn add_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
-        self.push_u64(a + b);
+    pub fn sub_u64_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        match a.checked_sub(b) {
+            Some(value) => self.push_u64(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
 
     #[inline]
-    pub fn add_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
-        self.push_u8((a + b) as u8);
+    pub fn sub_i8_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        match a.checked_sub(b) {
+            Some(value) => self.push_u8(value as u8),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn add_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
-        self.push_u16((a + b) as u16);
+    pub fn sub_i16_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        match a.checked_sub(b) {
+            Some(value) => self.push_u16(value as u16),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn add_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
-        self.push_u32((a + b) as u32);
+    pub fn sub_i32_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        match a.checked_sub(b) {
+            Some(value) => self.push_u32(value as u32),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn add_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
-        self.push_u64((a + b) as u64);
+    pub fn sub_i64_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        match a.checked_sub(b) {
+            Some(value) => self.push_u64(value as u64),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
 
     #[inline]
-    pub fn add_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
-        self.push_u32((a + b) as u32);
+    pub fn sub_u8_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.push_u8(a.saturating_sub(b));
+        Ok(())
     }
     #[inline]
-    pub fn add_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
-        self.push_u64((a + b) as u64);
+    pub fn sub_u16_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.push_u16(a.saturating_sub(b));
+        Ok(())
     }
-
-
     #[inline]
-    pub fn sub_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
-        self.push_u8(a - b);
+    pub fn sub_u32_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.push_u32(a.saturating_sub(b));
+        Ok(())
     }
     #[inline]
-    pub fn sub_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
-        self.push_u16(a - b);
+    pub fn sub_u64_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.push_u64(a.saturating_sub(b));
+        Ok(())
     }
+
     #[inline]
-    pub fn sub_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
-        self.push_u32(a - b);
+    pub fn sub_i8_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.saturating_sub(b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn sub_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
-        self.push_u64(a - b);
+    pub fn sub_i16_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.saturating_sub(b) as u16);
+        Ok(())
     }
-
     #[inline]
-    pub fn sub_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
-        self.push_u8((a - b) as u8);
+    pub fn sub_i32_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.saturating_sub(b) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn sub_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
-        self.push_u16((a - b) as u16);
+    pub fn sub_i64_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.saturating_sub(b) as u64);
+        Ok(())
     }
+
+
     #[inline]
-    pub fn sub_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
-        self.push_u32((a - b) as u32);
+    pub fn mul_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.push_u8(a.wrapping_mul(b));
+        Ok(())
     }
     #[inline]
-    pub fn sub_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
-        self.push_u64((a - b) as u64);
+    pub fn mul_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.push_u16(a.wrapping_mul(b));
+        Ok(())
     }
-
     #[inline]
-    pub fn sub_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
-        self.push_u32((a - b) as u32);
+    pub fn mul_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.push_u32(a.wrapping_mul(b));
+        Ok(())
     }
     #[inline]
-    pub fn sub_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
-        self.push_u64((a - b) as u64);
+    pub fn mul_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.push_u64(a.wrapping_mul(b));
+        Ok(())
     }
 
-
     #[inline]
-    pub fn mul_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
-        self.push_u8(a * b);
+    pub fn mul_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.wrapping_mul(b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn mul_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
-        self.push_u16(a * b);
+    pub fn mul_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.wrapping_mul(b) as u16);
+        Ok(())
     }
     #[inline]
-    pub fn mul_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
-        self.push_u32(a * b);
+    pub fn mul_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.wrapping_mul(b) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn mul_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
-        self.push_u64(a * b);
+    pub fn mul_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.wrapping_mul(b) as u64);
+        Ok(())
     }
 
     #[inline]
-    pub fn mul_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
-        self.push_u8((a * b) as u8);
+    pub fn mul_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
+        self.push_f32(a * b);
+        Ok(())
     }
     #[inline]
-    pub fn mul_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
-        self.push_u16((a * b) as u16);
+    pub fn mul_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
+        self.push_f64(a * b);
+        Ok(())
     }
+
     #[inline]
-    pub fn mul_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
-        self.push_u32((a * b) as u32);
+    pub fn mul_u8_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        match a.checked_mul(b) {
+            Some(value) => self.push_u8(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn mul_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
-        self.push_u64((a * b) as u64);
+    pub fn mul_u16_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        match a.checked_mul(b) {
+            Some(value) => self.push_u16(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
-
     #[inline]
-    pub fn mul_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
-        self.push_u32((a * b) as u32);
+    pub fn mul_u32_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        match a.checked_mul(b) {
+            Some(value) => self.push_u32(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn mul_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
-        self.push_u64((a * b) as u64);
+    pub fn mul_u64_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        match a.checked_mul(b) {
+            Some(value) => self.push_u64(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
 
-
     #[inline]
-    pub fn div_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
-        self.push_u8(a / b);
+    pub fn mul_i8_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        match a.checked_mul(b) {
+            Some(value) => self.push_u8(value as u8),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn div_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
-        self.push_u16(a / b);
+    pub fn mul_i16_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        match a.checked_mul(b) {
+            Some(value) => self.push_u16(value as u16),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn div_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
-        self.push_u32(a / b);
+    pub fn mul_i32_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        match a.checked_mul(b) {
+            Some(value) => self.push_u32(value as u32),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn div_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
-        self.push_u64(a / b);
+    pub fn mul_i64_checked(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        match a.checked_mul(b) {
+            Some(value) => self.push_u64(value as u64),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
 
     #[inline]
-    pub fn div_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
-        self.push_u8((a / b) as u8);
+    pub fn mul_u8_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.push_u8(a.saturating_mul(b));
+        Ok(())
     }
     #[inline]
-    pub fn div_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
-        self.push_u16((a / b) as u16);
+    pub fn mul_u16_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.push_u16(a.saturating_mul(b));
+        Ok(())
     }
     #[inline]
-    pub fn div_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
-        self.push_u32((a / b) as u32);
+    pub fn mul_u32_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.push_u32(a.saturating_mul(b));
+        Ok(())
     }
     #[inline]
-    pub fn div_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
-        self.push_u64((a / b) as u64);
+    pub fn mul_u64_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.push_u64(a.saturating_mul(b));
+        Ok(())
     }
 
     #[inline]
-    pub fn div_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
-        self.push_u32((a / b) as u32);
+    pub fn mul_i8_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.saturating_mul(b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn div_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
-        self.push_u64((a / b) as u64);
+    pub fn mul_i16_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.saturating_mul(b) as u16);
+        Ok(())
     }
-
-
     #[inline]
-    pub fn rem_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
-        self.push_u8(a % b);
+    pub fn mul_i32_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.saturating_mul(b) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn rem_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
-        self.push_u16(a % b);
+    pub fn mul_i64_sat(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.saturating_mul(b) as u64);
+        Ok(())
     }
+
+
     #[inline]
-    pub fn rem_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
-        self.push_u32(a % b);
+    pub fn div_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        let value = match a.checked_div(b) {
+            Some(value) => value,
+            None => {
+                self.divide_by_zero()?;
+                0
+            },
+        };
+        self.push_u8(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn div_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        let value = match a.checked_div(b) {
+            Some(value) => value,
+            None => {
+                self.divide_by_zero()?;
+                0
+            },
+        };
+        self.push_u16(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn div_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        let value = match a.checked_div(b) {
+            Some(value) => value,
+            None => {
+                self.divide_by_zero()?;
+                0
+            },
+        };
+        self.push_u32(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn div_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        let value = match a.checked_div(b) {
+            Some(value) => value,
+            None => {
+                self.divide_by_zero()?;
+                0
+            },
+        };
+        self.push_u64(value);
+        Ok(())
     }
+
     #[inline]
-    pub fn rem_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
-        self.push_u64(a % b);
+    pub fn div_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        let value = match a.checked_div(b) {
+            Some(value) => value,
+            None if b == 0 => {
+                self.divide_by_zero()?;
+                0
+            },
+            None => {
+                self.divide_overflow()?;
+                a.wrapping_div(b)
+            },
+        };
+        self.push_u8(value as u8);
+        Ok(())
+    }
+    #[inline]
+    pub fn div_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        let value = match a.checked_div(b) {
+            Some(value) => value,
+            None if b == 0 => {
+                self.divide_by_zero()?;
+                0
+            },
+            None => {
+                self.divide_overflow()?;
+                a.wrapping_div(b)
+            },
+        };
+        self.push_u16(value as u16);
+        Ok(())
+    }
+    #[inline]
+    pub fn div_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        let value = match a.checked_div(b) {
+            Some(value) => value,
+            None if b == 0 => {
+                self.divide_by_zero()?;
+                0
+            },
+            None => {
+                self.divide_overflow()?;
+                a.wrapping_div(b)
+            },
+        };
+        self.push_u32(value as u32);
+        Ok(())
+    }
+    #[inline]
+    pub fn div_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        let value = match a.checked_div(b) {
+            Some(value) => value,
+            None if b == 0 => {
+                self.divide_by_zero()?;
+                0
+            },
+            None => {
+                self.divide_overflow()?;
+                a.wrapping_div(b)
+            },
+        };
+        self.push_u64(value as u64);
+        Ok(())
     }
 
     #[inline]
-    pub fn rem_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
-        self.push_u8((a % b) as u8);
+    pub fn div_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
+        self.push_f32(a / b);
+        Ok(())
     }
     #[inline]
-    pub fn rem_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
-        self.push_u16((a % b) as u16);
+    pub fn div_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
+        self.push_f64(a / b);
+        Ok(())
     }
+
+
     #[inline]
-    pub fn rem_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
-        self.push_u32((a % b) as u32);
+    pub fn rem_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        let value = match a.checked_rem(b) {
+            Some(value) => value,
+            None => {
+                self.divide_by_zero()?;
+                0
+            },
+        };
+        self.push_u8(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn rem_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        let value = match a.checked_rem(b) {
+            Some(value) => value,
+            None => {
+                self.divide_by_zero()?;
+                0
+            },
+        };
+        self.push_u16(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn rem_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        let value = match a.checked_rem(b) {
+            Some(value) => value,
+            None => {
+                self.divide_by_zero()?;
+                0
+            },
+        };
+        self.push_u32(value);
+        Ok(())
+    }
+    #[inline]
+    pub fn rem_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        let value = match a.checked_rem(b) {
+            Some(value) => value,
+            None => {
+                self.divide_by_zero()?;
+                0
+            },
+        };
+        self.push_u64(value);
+        Ok(())
     }
+
     #[inline]
-    pub fn rem_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
-        self.push_u64((a % b) as u64);
+    pub fn rem_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        let value = match a.checked_rem(b) {
+            Some(value) => value,
+            None if b == 0 => {
+                self.divide_by_zero()?;
+                0
+            },
+            None => a.wrapping_rem(b),
+        };
+        self.push_u8(value as u8);
+        Ok(())
+    }
+    #[inline]
+    pub fn rem_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        let value = match a.checked_rem(b) {
+            Some(value) => value,
+            None if b == 0 => {
+                self.divide_by_zero()?;
+                0
+            },
+            None => a.wrapping_rem(b),
+        };
+        self.push_u16(value as u16);
+        Ok(())
+    }
+    #[inline]
+    pub fn rem_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        let value = match a.checked_rem(b) {
+            Some(value) => value,
+            None if b == 0 => {
+                self.divide_by_zero()?;
+                0
+            },
+            None => a.wrapping_rem(b),
+        };
+        self.push_u32(value as u32);
+        Ok(())
+    }
+    #[inline]
+    pub fn rem_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        let value = match a.checked_rem(b) {
+            Some(value) => value,
+            None if b == 0 => {
+                self.divide_by_zero()?;
+                0
+            },
+            None => a.wrapping_rem(b),
+        };
+        self.push_u64(value as u64);
+        Ok(())
     }
 
+    // fmod, the result has the sign of a and is exact
     #[inline]
-    pub fn rem_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
-        self.push_u32((a % b) as u32);
+    pub fn rem_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
+        self.push_f32(a % b);
+        Ok(())
     }
     #[inline]
-    pub fn rem_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
-        self.push_u64((a % b) as u64);
+    pub fn rem_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
+        self.push_f64(a % b);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn and_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
+    pub fn and_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
         self.push_u8(a & b);
+        Ok(())
     }
     #[inline]
-    pub fn and_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
+    pub fn and_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
         self.push_u16(a & b);
+        Ok(())
     }
     #[inline]
-    pub fn and_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
+    pub fn and_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
         self.push_u32(a & b);
+        Ok(())
     }
     #[inline]
-    pub fn and_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
+    pub fn and_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
         self.push_u64(a & b);
+        Ok(())
     }
 
     #[inline]
-    pub fn and_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
+    pub fn and_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
         self.push_u8((a & b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn and_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
+    pub fn and_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
         self.push_u16((a & b) as u16);
+        Ok(())
     }
     #[inline]
-    pub fn and_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
+    pub fn and_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
         self.push_u32((a & b) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn and_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
+    pub fn and_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
         self.push_u64((a & b) as u64);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn or_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
+    pub fn or_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
         self.push_u8(a | b);
+        Ok(())
     }
     #[inline]
-    pub fn or_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
+    pub fn or_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
         self.push_u16(a | b);
+        Ok(())
     }
     #[inline]
-    pub fn or_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
+    pub fn or_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
         self.push_u32(a | b);
+        Ok(())
     }
     #[inline]
-    pub fn or_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
+    pub fn or_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
         self.push_u64(a | b);
+        Ok(())
     }
 
     #[inline]
-    pub fn or_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
+    pub fn or_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
         self.push_u8((a | b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn or_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
+    pub fn or_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
         self.push_u16((a | b) as u16);
+        Ok(())
     }
     #[inline]
-    pub fn or_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
+    pub fn or_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
         self.push_u32((a | b) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn or_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
+    pub fn or_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
         self.push_u64((a | b) as u64);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn xor_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
+    pub fn xor_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
         self.push_u8(a ^ b);
+        Ok(())
     }
     #[inline]
-    pub fn xor_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
+    pub fn xor_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
         self.push_u16(a ^ b);
+        Ok(())
     }
     #[inline]
-    pub fn xor_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
+    pub fn xor_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
         self.push_u32(a ^ b);
+        Ok(())
     }
     #[inline]
-    pub fn xor_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
+    pub fn xor_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
         self.push_u64(a ^ b);
+        Ok(())
     }
 
     #[inline]
-    pub fn xor_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
+    pub fn xor_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
         self.push_u8((a ^ b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn xor_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
+    pub fn xor_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
         self.push_u16((a ^ b) as u16);
+        Ok(())
     }
     #[inline]
-    pub fn xor_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
+    pub fn xor_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
         self.push_u32((a ^ b) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn xor_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
+    pub fn xor_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
         self.push_u64((a ^ b) as u64);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn shl_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
-        self.push_u8(a << b);
+    pub fn shl_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.push_u8(a.wrapping_shl(b as u32));
+        Ok(())
     }
     #[inline]
-    pub fn shl_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
-        self.push_u16(a << b);
+    pub fn shl_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.push_u16(a.wrapping_shl(b as u32));
+        Ok(())
     }
     #[inline]
-    pub fn shl_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
-        self.push_u32(a << b);
+    pub fn shl_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.push_u32(a.wrapping_shl(b));
+        Ok(())
     }
     #[inline]
-    pub fn shl_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
-        self.push_u64(a << b);
+    pub fn shl_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.push_u64(a.wrapping_shl(b as u32));
+        Ok(())
     }
 
     #[inline]
-    pub fn shl_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
-        self.push_u8((a << b) as u8);
+    pub fn shl_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.wrapping_shl(b as u32) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn shl_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
-        self.push_u16((a << b) as u16);
+    pub fn shl_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.wrapping_shl(b as u32) as u16);
+        Ok(())
     }
     #[inline]
-    pub fn shl_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
-        self.push_u32((a << b) as u32);
+    pub fn shl_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.wrapping_shl(b as u32) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn shl_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
-        self.push_u64((a << b) as u64);
+    pub fn shl_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.wrapping_shl(b as u32) as u64);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn shr_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
-        self.push_u8(a >> b);
+    pub fn shr_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
+        self.push_u8(a.wrapping_shr(b as u32));
+        Ok(())
     }
     #[inline]
-    pub fn shr_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
-        self.push_u16(a >> b);
+    pub fn shr_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
+        self.push_u16(a.wrapping_shr(b as u32));
+        Ok(())
     }
     #[inline]
-    pub fn shr_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
-        self.push_u32(a >> b);
+    pub fn shr_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
+        self.push_u32(a.wrapping_shr(b));
+        Ok(())
     }
     #[inline]
-    pub fn shr_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
-        self.push_u64(a >> b);
+    pub fn shr_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
+        self.push_u64(a.wrapping_shr(b as u32));
+        Ok(())
     }
 
     #[inline]
-    pub fn shr_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
-        self.push_u8((a >> b) as u8);
+    pub fn shr_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.wrapping_shr(b as u32) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn shr_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
-        self.push_u16((a >> b) as u16);
+    pub fn shr_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.wrapping_shr(b as u32) as u16);
+        Ok(())
     }
     #[inline]
-    pub fn shr_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
-        self.push_u32((a >> b) as u32);
+    pub fn shr_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.wrapping_shr(b as u32) as u32);
+        Ok(())
     }
     #[inline]
-    pub fn shr_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
-        self.push_u64((a >> b) as u64);
+    pub fn shr_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.wrapping_shr(b as u32) as u64);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn not_u8(&mut self) {
-        let a = self.pop_u8();
+    pub fn not_u8(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()?;
         self.push_u8(!a);
+        Ok(())
     }
     #[inline]
-    pub fn not_u16(&mut self) {
-        let a = self.pop_u16();
+    pub fn not_u16(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()?;
         self.push_u16(!a);
+        Ok(())
     }
     #[inline]
-    pub fn not_u32(&mut self) {
-        let a = self.pop_u32();
+    pub fn not_u32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()?;
         self.push_u32(!a);
+        Ok(())
     }
     #[inline]
-    pub fn not_u64(&mut self) {
-        let a = self.pop_u64();
+    pub fn not_u64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
         self.push_u64(!a);
+        Ok(())
     }
 
     #[inline]
-    pub fn not_i8(&mut self) {
-        let a = self.pop_u8() as i8;
+    pub fn not_i8(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()? as i8;
         self.push_u8(!a as u8);
+        Ok(())
     }
     #[inline]
-    pub fn not_i16(&mut self) {
-        let a = self.pop_u16() as i16;
+    pub fn not_i16(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()? as i16;
         self.push_u16(!a as u16);
+        Ok(())
     }
     #[inline]
-    pub fn not_i32(&mut self) {
-        let a = self.pop_u32() as i32;
+    pub fn not_i32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()? as i32;
         self.push_u32(!a as u32);
+        Ok(())
     }
     #[inline]
-    pub fn not_i64(&mut self) {
-        let a = self.pop_u64() as i64;
+    pub fn not_i64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()? as i64;
         self.push_u64(!a as u64);
+        Ok(())
+    }
+
+
+    #[inline]
+    pub fn neg_u8(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()?;
+        self.push_u8(a.wrapping_neg());
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_u16(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()?;
+        self.push_u16(a.wrapping_neg());
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_u32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()?;
+        self.push_u32(a.wrapping_neg());
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_u64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
+        self.push_u64(a.wrapping_neg());
+        Ok(())
     }
 
+    #[inline]
+    pub fn neg_i8(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.wrapping_neg() as u8);
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_i16(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.wrapping_neg() as u16);
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_i32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.wrapping_neg() as u32);
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_i64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.wrapping_neg() as u64);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn neg_f32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_f32()?;
+        self.push_f32(-a);
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_f64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_f64()?;
+        self.push_f64(-a);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn neg_u8_checked(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()?;
+        match a.checked_neg() {
+            Some(value) => self.push_u8(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_u16_checked(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()?;
+        match a.checked_neg() {
+            Some(value) => self.push_u16(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_u32_checked(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()?;
+        match a.checked_neg() {
+            Some(value) => self.push_u32(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_u64_checked(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
+        match a.checked_neg() {
+            Some(value) => self.push_u64(value),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
+    }
 
     #[inline]
-    pub fn neg_u8(&mut self) {
-        let a = self.pop_u8() as i8;
-        self.push_u8(-a as u8);
+    pub fn neg_i8_checked(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()? as i8;
+        match a.checked_neg() {
+            Some(value) => self.push_u8(value as u8),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn neg_u16(&mut self) {
-        let a = self.pop_u16() as i16;
-        self.push_u16(-a as u16);
+    pub fn neg_i16_checked(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()? as i16;
+        match a.checked_neg() {
+            Some(value) => self.push_u16(value as u16),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn neg_u32(&mut self) {
-        let a = self.pop_u32() as i32;
-        self.push_u32(-a as u32);
+    pub fn neg_i32_checked(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()? as i32;
+        match a.checked_neg() {
+            Some(value) => self.push_u32(value as u32),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
     #[inline]
-    pub fn neg_u64(&mut self) {
-        let a = self.pop_u64() as i64;
-        self.push_u64(-a as u64);
+    pub fn neg_i64_checked(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()? as i64;
+        match a.checked_neg() {
+            Some(value) => self.push_u64(value as u64),
+            None => return Err(self.trap(TrapKind::Overflow)),
+        }
+        Ok(())
     }
 
     #[inline]
-    pub fn neg_i8(&mut self) {
-        let a = self.pop_u8() as i8;
-        self.push_u8(-a as u8);
+    pub fn neg_u8_sat(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()?;
+        self.push_u8(0u8.saturating_sub(a));
+        Ok(())
     }
     #[inline]
-    pub fn neg_i16(&mut self) {
-        let a = self.pop_u16() as i16;
-        self.push_u16(-a as u16);
+    pub fn neg_u16_sat(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()?;
+        self.push_u16(0u16.saturating_sub(a));
+        Ok(())
     }
     #[inline]
-    pub fn neg_i32(&mut self) {
-        let a = self.pop_u32() as i32;
-        self.push_u32(-a as u32);
+    pub fn neg_u32_sat(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()?;
+        self.push_u32(0u32.saturating_sub(a));
+        Ok(())
     }
     #[inline]
-    pub fn neg_i64(&mut self) {
-        let a = self.pop_u64() as i64;
-        self.push_u64(-a as u64);
+    pub fn neg_u64_sat(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
+        self.push_u64(0u64.saturating_sub(a));
+        Ok(())
     }
 
     #[inline]
-    pub fn neg_f32(&mut self) {
-        let a = self.pop_u32() as f32;
-        self.push_u32(-a as u32);
+    pub fn neg_i8_sat(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()? as i8;
+        self.push_u8(a.saturating_neg() as u8);
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_i16_sat(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()? as i16;
+        self.push_u16(a.saturating_neg() as u16);
+        Ok(())
     }
     #[inline]
-    pub fn neg_f64(&mut self) {
-        let a = self.pop_u64() as f64;
-        self.push_u64(-a as u64);
+    pub fn neg_i32_sat(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()? as i32;
+        self.push_u32(a.saturating_neg() as u32);
+        Ok(())
+    }
+    #[inline]
+    pub fn neg_i64_sat(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()? as i64;
+        self.push_u64(a.saturating_neg() as u64);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn lt_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
+    pub fn lt_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lt_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
+    pub fn lt_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lt_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
+    pub fn lt_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lt_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
+    pub fn lt_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn lt_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
+    pub fn lt_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lt_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
+    pub fn lt_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lt_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
+    pub fn lt_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lt_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
+    pub fn lt_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn lt_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
+    pub fn lt_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lt_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
+    pub fn lt_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
         self.push_u8((a < b) as u8);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn gt_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
+    pub fn gt_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gt_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
+    pub fn gt_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gt_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
+    pub fn gt_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gt_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
+    pub fn gt_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn gt_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
+    pub fn gt_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gt_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
+    pub fn gt_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gt_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
+    pub fn gt_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gt_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
+    pub fn gt_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn gt_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
+    pub fn gt_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gt_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
+    pub fn gt_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
         self.push_u8((a > b) as u8);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn lte_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
+    pub fn lte_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lte_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
+    pub fn lte_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lte_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
+    pub fn lte_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lte_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
+    pub fn lte_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn lte_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
+    pub fn lte_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lte_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
+    pub fn lte_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lte_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
+    pub fn lte_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lte_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
+    pub fn lte_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn lte_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
+    pub fn lte_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn lte_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
+    pub fn lte_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
         self.push_u8((a <= b) as u8);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn gte_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
+    pub fn gte_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gte_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
+    pub fn gte_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gte_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
+    pub fn gte_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gte_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
+    pub fn gte_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn gte_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
+    pub fn gte_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gte_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
+    pub fn gte_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gte_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
+    pub fn gte_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gte_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
+    pub fn gte_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn gte_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
+    pub fn gte_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn gte_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
+    pub fn gte_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
         self.push_u8((a >= b) as u8);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn eq_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
+    pub fn eq_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn eq_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
+    pub fn eq_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn eq_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
+    pub fn eq_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn eq_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
+    pub fn eq_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn eq_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
+    pub fn eq_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn eq_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
+    pub fn eq_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn eq_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
+    pub fn eq_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn eq_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
+    pub fn eq_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn eq_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
+    pub fn eq_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn eq_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
+    pub fn eq_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
         self.push_u8((a == b) as u8);
+        Ok(())
     }
 
 
     #[inline]
-    pub fn neq_u8(&mut self) {
-        let b = self.pop_u8();
-        let a = self.pop_u8();
+    pub fn neq_u8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()?;
+        let a = self.pop_u8()?;
         self.push_u8((a != b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn neq_u16(&mut self) {
-        let b = self.pop_u16();
-        let a = self.pop_u16();
+    pub fn neq_u16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()?;
+        let a = self.pop_u16()?;
         self.push_u8((a != b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn neq_u32(&mut self) {
-        let b = self.pop_u32();
-        let a = self.pop_u32();
+    pub fn neq_u32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()?;
+        let a = self.pop_u32()?;
         self.push_u8((a != b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn neq_u64(&mut self) {
-        let b = self.pop_u64();
-        let a = self.pop_u64();
+    pub fn neq_u64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()?;
+        let a = self.pop_u64()?;
         self.push_u8((a != b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn neq_i8(&mut self) {
-        let b = self.pop_u8() as i8;
-        let a = self.pop_u8() as i8;
+    pub fn neq_i8(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u8()? as i8;
+        let a = self.pop_u8()? as i8;
         self.push_u8((a != b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn neq_i16(&mut self) {
-        let b = self.pop_u16() as i16;
-        let a = self.pop_u16() as i16;
+    pub fn neq_i16(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u16()? as i16;
+        let a = self.pop_u16()? as i16;
         self.push_u8((a != b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn neq_i32(&mut self) {
-        let b = self.pop_u32() as i32;
-        let a = self.pop_u32() as i32;
+    pub fn neq_i32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u32()? as i32;
+        let a = self.pop_u32()? as i32;
         self.push_u8((a != b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn neq_i64(&mut self) {
-        let b = self.pop_u64() as i64;
-        let a = self.pop_u64() as i64;
+    pub fn neq_i64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_u64()? as i64;
+        let a = self.pop_u64()? as i64;
         self.push_u8((a != b) as u8);
+        Ok(())
     }
 
     #[inline]
-    pub fn neq_f32(&mut self) {
-        let b = self.pop_u32() as f32;
-        let a = self.pop_u32() as f32;
+    pub fn neq_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
         self.push_u8((a != b) as u8);
+        Ok(())
     }
     #[inline]
-    pub fn neq_f64(&mut self) {
-        let b = self.pop_u64() as f64;
-        let a = self.pop_u64() as f64;
+    pub fn neq_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
         self.push_u8((a != b) as u8);
+        Ok(())
+    }
+
+
+    #[inline]
+    pub fn ext_u8(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()?;
+        self.push_u64(a as u64);
+        Ok(())
+    }
+    #[inline]
+    pub fn ext_u16(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()?;
+        self.push_u64(a as u64);
+        Ok(())
+    }
+    #[inline]
+    pub fn ext_u32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()?;
+        self.push_u64(a as u64);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn ext_i8(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u8()? as i8;
+        self.push_u64(a as i64 as u64);
+        Ok(())
+    }
+    #[inline]
+    pub fn ext_i16(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u16()? as i16;
+        self.push_u64(a as i64 as u64);
+        Ok(())
+    }
+    #[inline]
+    pub fn ext_i32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()? as i32;
+        self.push_u64(a as i64 as u64);
+        Ok(())
+    }
+
+
+    #[inline]
+    pub fn wrap_u8(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
+        self.push_u8(a as u8);
+        Ok(())
+    }
+    #[inline]
+    pub fn wrap_u16(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
+        self.push_u16(a as u16);
+        Ok(())
+    }
+    #[inline]
+    pub fn wrap_u32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
+        self.push_u32(a as u32);
+        Ok(())
+    }
+
+
+    #[inline]
+    pub fn cvt_i64_f32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()? as i64;
+        self.push_f32(a as f32);
+        Ok(())
+    }
+    #[inline]
+    pub fn cvt_u64_f32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
+        self.push_f32(a as f32);
+        Ok(())
+    }
+    #[inline]
+    pub fn cvt_i64_f64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()? as i64;
+        self.push_f64(a as f64);
+        Ok(())
+    }
+    #[inline]
+    pub fn cvt_u64_f64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
+        self.push_f64(a as f64);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn cvt_f32_i64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_f32()?;
+        self.push_u64(a as i64 as u64);
+        Ok(())
+    }
+    #[inline]
+    pub fn cvt_f32_u64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_f32()?;
+        self.push_u64(a as u64);
+        Ok(())
+    }
+    #[inline]
+    pub fn cvt_f64_i64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_f64()?;
+        self.push_u64(a as i64 as u64);
+        Ok(())
+    }
+    #[inline]
+    pub fn cvt_f64_u64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_f64()?;
+        self.push_u64(a as u64);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn promote_f32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_f32()?;
+        self.push_f64(a as f64);
+        Ok(())
+    }
+    #[inline]
+    pub fn demote_f64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_f64()?;
+        self.push_f32(a as f32);
+        Ok(())
+    }
+
+    // clears the sign bit, NaN stays NaN
+    #[inline]
+    pub fn abs_f32(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u32()?;
+        self.push_u32(a & 0x7fff_ffff);
+        Ok(())
+    }
+    #[inline]
+    pub fn abs_f64(&mut self) -> Result<(), Trap> {
+        let a = self.pop_u64()?;
+        self.push_u64(a & 0x7fff_ffff_ffff_ffff);
+        Ok(())
+    }
+
+    // a NaN operand yields the other operand
+    #[inline]
+    pub fn min_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
+        self.push_f32(a.min(b));
+        Ok(())
+    }
+    #[inline]
+    pub fn min_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
+        self.push_f64(a.min(b));
+        Ok(())
+    }
+
+    #[inline]
+    pub fn max_f32(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f32()?;
+        let a = self.pop_f32()?;
+        self.push_f32(a.max(b));
+        Ok(())
+    }
+    #[inline]
+    pub fn max_f64(&mut self) -> Result<(), Trap> {
+        let b = self.pop_f64()?;
+        let a = self.pop_f64()?;
+        self.push_f64(a.max(b));
+        Ok(())
     }
 }
@@ -0,0 +1,208 @@
+use core::fmt;
+
+use vector::Vector;
+use stack::Stack;
+
+use process::Process;
+use state::State;
+use status::Status;
+use trap::{Trap, TrapKind};
+use vm;
+
+
+// identifies a process for as long as the scheduler lives, never reused
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Pid(pub u64);
+
+impl fmt::Display for Pid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+
+#[derive(Debug)]
+struct Slot<'a> {
+    pid: Pid,
+    process: Process<'a>,
+    // ran out of fuel, skipped until refueled
+    parked: bool,
+}
+
+
+// round robin over many processes, each runs for at most slice instructions
+// before the next one gets its turn
+//
+// let mut scheduler = Scheduler::new(1000);
+// let pid = scheduler.add(Process::new(&program));
+// while let Some((pid, result)) = scheduler.run_slice() {
+//     ...
+// }
+#[derive(Debug)]
+pub struct Scheduler<'a> {
+    // in round robin order
+    slots: Vector<Slot<'a>>,
+    // slot index the next turn starts looking from
+    next: usize,
+    next_pid: u64,
+    slice: usize,
+}
+
+impl<'a> Scheduler<'a> {
+    #[inline]
+    pub fn new(slice: usize) -> Self {
+        Scheduler {
+            slots: Vector::new(),
+            next: 0,
+            next_pid: 0,
+            slice: slice,
+        }
+    }
+
+    #[inline]
+    pub fn get_slice(&self) -> usize {self.slice}
+    #[inline]
+    pub fn set_slice(&mut self, slice: usize) {
+        self.slice = slice;
+    }
+
+    // takes the process into the rotation, it gets its first turn after
+    // the processes already added
+    #[inline]
+    pub fn add(&mut self, process: Process<'a>) -> Pid {
+        let pid = Pid(self.next_pid);
+        self.next_pid += 1;
+
+        self.slots.push(Slot {
+            pid: pid,
+            process: process,
+            parked: false,
+        });
+        pid
+    }
+    // takes the process out of the rotation
+    #[inline]
+    pub fn remove(&mut self, pid: Pid) -> Option<Process<'a>> {
+        self.index(pid).map(|index| self.remove_index(index))
+    }
+
+    // processes not yet reaped, Waiting and parked ones included
+    #[inline]
+    pub fn len(&self) -> usize {self.slots.len()}
+    #[inline]
+    pub fn is_empty(&self) -> bool {self.slots.is_empty()}
+    #[inline]
+    pub fn pids<'b>(&'b self) -> impl Iterator<Item = Pid> + 'b {
+        self.slots.iter().map(|slot| slot.pid)
+    }
+
+    #[inline]
+    pub fn get(&self, pid: Pid) -> Option<&Process<'a>> {
+        self.index(pid).map(|index| &self.slots[index].process)
+    }
+    #[inline]
+    pub fn get_mut(&mut self, pid: Pid) -> Option<&mut Process<'a>> {
+        self.index(pid).map(move |index| &mut self.slots[index].process)
+    }
+
+    // wakes the process if it is Waiting, returns whether it was
+    #[inline]
+    pub fn wake(&mut self, pid: Pid) -> bool {
+        match self.get_mut(pid) {
+            Some(process) if process.get_state() == State::Waiting => {
+                process.wake();
+                true
+            },
+            _ => false,
+        }
+    }
+    // whether the process ran out of fuel and waits to be refueled
+    #[inline]
+    pub fn is_parked(&self, pid: Pid) -> bool {
+        match self.index(pid) {
+            Some(index) => self.slots[index].parked,
+            None => false,
+        }
+    }
+    // adds fuel to the process and puts it back into the rotation if it was
+    // parked, returns the fuel it has left, None if there is no such process
+    #[inline]
+    pub fn refuel(&mut self, pid: Pid, fuel: u64) -> Option<u64> {
+        let index = self.index(pid)?;
+        self.slots[index].parked = false;
+        Some(self.slots[index].process.refuel(fuel))
+    }
+
+    // wakes every process waiting on event, returns how many it woke
+    #[inline]
+    pub fn notify(&mut self, event: u64) -> usize {
+        let mut woken = 0;
+
+        for slot in self.slots.iter_mut() {
+            if slot.process.get_state() == State::Waiting && slot.process.get_event() == Some(event) {
+                slot.process.wake();
+                woken += 1;
+            }
+        }
+        woken
+    }
+
+    // runs the next process that is neither Waiting nor parked for one
+    // slice, None when there is none, a process that halted, finished or
+    // trapped is reaped, one out of fuel stays Running but is parked until
+    // refuel, the child of a spawn is added after the others and its pid
+    // pushed onto the parent's stack
+    pub fn run_slice(&mut self) -> Option<(Pid, Result<Status, Trap>)> {
+        let index = self.runnable()?;
+        let pid = self.slots[index].pid;
+
+        let result = vm::run_for(&mut self.slots[index].process, self.slice);
+        self.next = index + 1;
+
+        match result {
+            Ok(Status::Spawned) => {
+                let child = self.slots[index].process.take_spawned();
+                if let Some(child) = child {
+                    let child = self.add(child);
+                    self.slots[index].process.push_u64(child.0);
+                }
+            },
+            Err(trap) if trap.get_kind() == TrapKind::OutOfFuel => {
+                self.slots[index].parked = true;
+            },
+            _ if self.slots[index].process.get_state() == State::Terminated => {
+                self.remove_index(index);
+            },
+            _ => (),
+        }
+        Some((pid, result))
+    }
+
+    #[inline]
+    fn index(&self, pid: Pid) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.pid == pid)
+    }
+
+    // first slot from next on, wrapping around, that is neither Waiting nor
+    // parked
+    #[inline]
+    fn runnable(&self) -> Option<usize> {
+        let len = self.slots.len();
+
+        (0..len).map(|offset| (self.next + offset) % len)
+            .find(|index| {
+                let slot = &self.slots[*index];
+                !slot.parked && slot.process.get_state() != State::Waiting
+            })
+    }
+
+    // keeps the rotation order of the remaining slots
+    #[inline]
+    fn remove_index(&mut self, index: usize) -> Process<'a> {
+        self.slots[index..].rotate_left(1);
+        if self.next > index {
+            self.next -= 1;
+        }
+        self.slots.pop().unwrap().process
+    }
+}
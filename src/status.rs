@@ -0,0 +1,19 @@
+// how vm::run, run_for or step stopped, a trap is returned as its error
+// instead
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Status {
+    // halt, with exit code 0, or halt_with and the exit code it popped
+    Halted(u32),
+    // ran past the last instruction
+    Finished,
+    // pause, the process is still Running and continues where it left off
+    Yielded,
+    // wait, the process is Waiting until it is woken
+    Waiting,
+    // spawn, the process is still Running but is not evaluated until the
+    // caller takes the child with Process::take_spawned, it pushes the
+    // child's pid before running it again
+    Spawned,
+    // evaluated as many instructions as run_for allowed, still Running
+    OutOfBudget,
+}
@@ -0,0 +1,82 @@
+use core::fmt;
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TrapKind {
+    // popped or peeked past the bottom of the stack
+    StackUnderflow,
+    // program ended in the middle of an instruction
+    TruncatedProgram,
+    // operand prefix is not a type_* (raw byte)
+    InvalidType(u8),
+    // operand prefix is not a size_* (raw byte)
+    InvalidSize(u8),
+    // varint operand does not fit in 64 bits
+    InvalidVarint,
+    // opcode is not an executable instruction (raw byte)
+    InvalidInstr(u8),
+    DivideByZero,
+    // checked arithmetic overflowed
+    Overflow,
+    // memory access outside of process memory (address)
+    MemoryOutOfBounds(usize),
+    // ret without a matching call
+    CallStackUnderflow,
+    // jmp_indirect or call_indirect target is not an entry point (target)
+    InvalidTarget(usize),
+    // local_get or local_set outside of the current frame (offset)
+    InvalidLocal(usize),
+    // fuel left does not cover the instruction, it was not evaluated and
+    // runs once the process is refueled
+    OutOfFuel,
+}
+
+impl fmt::Display for TrapKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrapKind::StackUnderflow => write!(f, "stack underflow"),
+            TrapKind::TruncatedProgram => write!(f, "unexpected end of program"),
+            TrapKind::InvalidType(byte) => write!(f, "invalid operand type {}", byte),
+            TrapKind::InvalidSize(byte) => write!(f, "invalid operand size {}", byte),
+            TrapKind::InvalidVarint => write!(f, "invalid varint operand"),
+            TrapKind::InvalidInstr(byte) => write!(f, "invalid instruction {}", byte),
+            TrapKind::DivideByZero => write!(f, "divide by zero"),
+            TrapKind::Overflow => write!(f, "arithmetic overflow"),
+            TrapKind::MemoryOutOfBounds(address) => write!(f, "memory access out of bounds {}", address),
+            TrapKind::CallStackUnderflow => write!(f, "call stack underflow"),
+            TrapKind::InvalidTarget(target) => write!(f, "invalid indirect target {}", target),
+            TrapKind::InvalidLocal(offset) => write!(f, "invalid local {}", offset),
+            TrapKind::OutOfFuel => write!(f, "out of fuel"),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Trap {
+    kind: TrapKind,
+    program_counter: usize,
+}
+
+impl Trap {
+
+    #[inline]
+    pub fn new(kind: TrapKind, program_counter: usize) -> Self {
+        Trap {
+            kind: kind,
+            program_counter: program_counter,
+        }
+    }
+
+    #[inline]
+    pub fn get_kind(&self) -> TrapKind {self.kind}
+    // offset of the instruction that faulted
+    #[inline]
+    pub fn get_program_counter(&self) -> usize {self.program_counter}
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.program_counter)
+    }
+}
@@ -0,0 +1,148 @@
+use core::convert::TryFrom;
+use core::fmt;
+
+use vector::Vector;
+use stack::Stack;
+
+use instrs::Instr;
+use decoder::{self, DecodeErrorKind, is_prefix};
+use encoding;
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VerifyErrorKind {
+    // byte is not an executable instruction, or cannot follow checked or sat
+    InvalidInstr(u8),
+    // byte is not a type_* prefix
+    InvalidType(u8),
+    // byte is not a size_* prefix
+    InvalidSize(u8),
+    // varint operand does not fit in 64 bits
+    InvalidVarint,
+    // program ends inside an instruction
+    TruncatedProgram,
+    // jmp, if_jmp or call target is not the offset of an instruction, or is
+    // the offset of one following checked or sat
+    InvalidTarget(usize),
+}
+
+impl From<DecodeErrorKind> for VerifyErrorKind {
+    #[inline]
+    fn from(kind: DecodeErrorKind) -> Self {
+        match kind {
+            DecodeErrorKind::InvalidInstr(byte) => VerifyErrorKind::InvalidInstr(byte),
+            DecodeErrorKind::InvalidType(byte) => VerifyErrorKind::InvalidType(byte),
+            DecodeErrorKind::InvalidSize(byte) => VerifyErrorKind::InvalidSize(byte),
+            DecodeErrorKind::InvalidVarint => VerifyErrorKind::InvalidVarint,
+            DecodeErrorKind::Truncated => VerifyErrorKind::TruncatedProgram,
+        }
+    }
+}
+
+impl fmt::Display for VerifyErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerifyErrorKind::InvalidInstr(byte) => write!(f, "invalid instruction {}", byte),
+            VerifyErrorKind::InvalidType(byte) => write!(f, "invalid operand type {}", byte),
+            VerifyErrorKind::InvalidSize(byte) => write!(f, "invalid operand size {}", byte),
+            VerifyErrorKind::InvalidVarint => write!(f, "invalid varint operand"),
+            VerifyErrorKind::TruncatedProgram => write!(f, "unexpected end of program"),
+            VerifyErrorKind::InvalidTarget(target) => write!(f, "invalid jump target {}", target),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct VerifyError {
+    kind: VerifyErrorKind,
+    offset: usize,
+}
+
+impl VerifyError {
+    #[inline]
+    pub fn new(kind: VerifyErrorKind, offset: usize) -> Self {
+        VerifyError {
+            kind: kind,
+            offset: offset,
+        }
+    }
+
+    #[inline]
+    pub fn get_kind(&self) -> VerifyErrorKind {self.kind}
+    // offset of the offending byte, or of the jumping instruction
+    #[inline]
+    pub fn get_offset(&self) -> usize {self.offset}
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.offset)
+    }
+}
+
+
+// checks that program decodes into a stream of valid instructions and that
+// every immediate jmp, if_jmp and call target, absolute or relative, is the
+// offset of one of them that is not prefixed, returns every problem found
+// ordered by offset, decoding stops at the first instruction that does not
+// decode as its length is unknown
+pub fn verify(program: &[u8]) -> Result<(), Vector<VerifyError>> {
+    let mut errors = Vector::new();
+    // offsets of the instructions a branch may target
+    let mut offsets = Vector::new();
+    let mut branches = Vector::new();
+    let mut offset = encoding::read_header(program).1;
+    // offset decoding stopped at
+    let mut stopped = None;
+    let mut prefixed = false;
+
+    while offset < program.len() {
+        let decoded = match decoder::decode(program, offset) {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                errors.push(VerifyError::new(error.get_kind().into(), error.get_offset()));
+                stopped = Some(offset);
+                break;
+            },
+        };
+        if !prefixed {
+            offsets.push(offset);
+        }
+        prefixed = is_prefix(decoded.get_instr());
+
+        if prefixed {
+            let next = decoded.get_end();
+
+            match program.get(next) {
+                Some(&opcode) => match Instr::try_from(opcode) {
+                    Ok(instr) if instr.is_prefixable() => (),
+                    _ => errors.push(VerifyError::new(VerifyErrorKind::InvalidInstr(opcode), next)),
+                },
+                None => errors.push(VerifyError::new(VerifyErrorKind::TruncatedProgram, next)),
+            }
+        }
+        if let Some(target) = decoded.get_target() {
+            branches.push((offset, target));
+        }
+
+        offset = decoded.get_end();
+    }
+
+    for &(offset, target) in branches.iter() {
+        // targets from where decoding stopped on cannot be checked
+        let checked = match stopped {
+            Some(stopped) => target < stopped,
+            None => true,
+        };
+        if checked && offsets.binary_search(&target).is_err() {
+            errors.push(VerifyError::new(VerifyErrorKind::InvalidTarget(target), offset));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        errors.sort_unstable_by_key(|error| error.offset);
+        Err(errors)
+    }
+}
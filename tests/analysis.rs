@@ -0,0 +1,177 @@
+extern crate vm;
+
+
+use vm::{ValueType, StackErrorKind};
+
+
+fn errors(source: &str) -> Vec<(StackErrorKind, usize)> {
+    let program = vm::assemble(source).unwrap();
+    vm::analyze(&program).get_errors().iter()
+        .map(|error| (error.get_kind(), error.get_offset()))
+        .collect()
+}
+
+
+#[test]
+fn test_analyze_shapes() {
+    let program = vm::assemble("
+        push_u16 1
+        push_u16 2
+        add_u16
+        ext_u16
+        push_u64 0
+        save_u64
+        halt
+    ").unwrap();
+    let analysis = vm::analyze(&program);
+
+    assert!(analysis.is_ok());
+    assert_eq!(analysis.get_max_depth(), Some(16));
+    assert_eq!(analysis.get_shape(0), Some(&[][..]));
+    // before ext_u16
+    assert_eq!(analysis.get_shape(9), Some(&[ValueType::U16][..]));
+    // before save_u64
+    assert_eq!(analysis.get_shape(14), Some(&[ValueType::U64, ValueType::U64][..]));
+    assert_eq!(analysis.get_shape(1), None);
+}
+
+#[test]
+fn test_analyze_mismatch() {
+    assert_eq!(errors("push_u16 7\npop_u32"), vec![
+        (StackErrorKind::WidthMismatch(ValueType::U32, ValueType::U16), 4),
+    ]);
+    // signedness is not checked, only widths
+    assert_eq!(errors("push_u16 7\npop_i16"), vec![]);
+
+    assert_eq!(errors("push_u8 1\nadd_u8"), vec![(StackErrorKind::StackUnderflow, 4)]);
+    assert_eq!(errors("ret"), vec![(StackErrorKind::CallStackUnderflow, 0)]);
+}
+
+#[test]
+fn test_analyze_joins() {
+    // the taken branch has a u8 on the stack, the other a u16
+    assert_eq!(errors("
+        push_u8 1
+        push_u8 1
+        if_jmp end
+        pop_u8
+        push_u16 1
+        end:
+        halt
+    "), vec![(StackErrorKind::ShapeMismatch, 17)]);
+
+    // loops must keep the stack balanced
+    assert_eq!(errors("
+        loop:
+        push_u8 1
+        push_u8 1
+        if_jmp loop
+    "), vec![(StackErrorKind::ShapeMismatch, 0)]);
+
+    assert_eq!(errors("
+        push_u8 3
+        loop:
+        push_u8 1
+        sub_u8
+        copy_u8
+        if_jmp loop
+        pop_u8
+    "), vec![]);
+}
+
+#[test]
+fn test_analyze_calls() {
+    let program = vm::assemble("
+        push_u32 1
+        push_u8 2
+        call double
+        push_u64 5
+        pop_u64
+        pop_u8
+        pop_u8
+        pop_u32
+        halt
+
+        push_u16 9
+        push_u8 1
+        call double
+        pop_u8
+        pop_u8
+        pop_u16
+        halt
+
+        // u8 -> u8, u8
+        double:
+            copy_u8
+            ret
+    ").unwrap();
+    let analysis = vm::analyze(&program);
+
+    assert!(analysis.is_ok());
+    // u32, u8, u8, u64
+    assert_eq!(analysis.get_max_depth(), Some(14));
+
+    assert_eq!(errors("
+        push_u16 1
+        call double
+        halt
+        double:
+            copy_u8
+            ret
+    "), vec![(StackErrorKind::WidthMismatch(ValueType::U8, ValueType::U16), 4)]);
+
+    assert_eq!(errors("
+        call two
+        halt
+        two:
+            push_u8 0
+            if_jmp one
+            push_u8 1
+            ret
+        one:
+            push_u16 1
+            ret
+    "), vec![(StackErrorKind::ShapeMismatch, 22)]);
+}
+
+#[test]
+fn test_analyze_recursion() {
+    let program = vm::assemble("
+        push_u8 1
+        call f
+        halt
+        f:
+            copy_u8
+            if_jmp done
+            call f
+        done:
+            ret
+    ").unwrap();
+    let analysis = vm::analyze(&program);
+
+    assert!(analysis.is_ok());
+    assert_eq!(analysis.get_max_depth(), None);
+}
+
+#[test]
+fn test_analyze_huge_counts() {
+    // operand sized pops are bounded by what the stack can hold instead of
+    // being looped over
+    let max = u64::MAX;
+    for source in [
+        format!("push_u8 1\npick_u8 {}", max),
+        format!("push_u8 1\nroll_u64 {}", max - 1),
+        format!("push_u8 1\ndrop_bytes {}", max),
+        format!("call f\nhalt\nf:\npick_u8 {}\nret", max),
+        format!("call f\nhalt\nf:\ndrop_bytes {}\nret", max / 2),
+        format!("push_u8 1\ncall_args f, {}\nhalt\nf:\nret", max),
+    ].iter() {
+        let kinds: Vec<_> = errors(source).into_iter().map(|(kind, _)| kind).collect();
+        assert_eq!(kinds, vec![StackErrorKind::StackUnderflow], "{}", source);
+    }
+
+    let kinds: Vec<_> = errors(&format!("push_u8 1\nenter {}\nenter {}\nhalt", max, max)).into_iter().map(|(kind, _)| kind).collect();
+    assert_eq!(kinds, vec![StackErrorKind::UnknownFrame]);
+    let program = vm::assemble(&format!("enter {}\ncall f\nhalt\nf:\nenter {}\nret", max / 2, max / 2)).unwrap();
+    assert!(vm::analyze(&program).get_max_depth().is_some());
+}
@@ -0,0 +1,140 @@
+extern crate vm;
+
+
+use vm::{Process, Status, Instr, AssembleErrorKind};
+
+
+static SOURCE: &'static str = "
+call function
+jmp function_end
+
+function:
+    push_u8 0
+    loop:
+        push_u8 1
+        add_u8
+        copy_u8
+        push_u8 5
+        neq_u8,
+        if_jmp loop
+    loop_end:
+
+    pop_u8
+    ret
+function_end:
+
+halt
+";
+
+
+#[test]
+fn test_assemble_program() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    assert_eq!(&program[..], &[
+        Instr::call as u8, Instr::type_int as u8, Instr::size_8 as u8, 8,
+        Instr::jmp as u8, Instr::type_int as u8, Instr::size_8 as u8, 29,
+
+        Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 0,
+
+        Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 1,
+        Instr::add_u8 as u8,
+
+        Instr::copy_u8 as u8,
+        Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 5,
+        Instr::neq_u8 as u8,
+        Instr::if_jmp as u8, Instr::type_int as u8, Instr::size_8 as u8, 12,
+        Instr::pop_u8 as u8,
+        Instr::ret as u8,
+
+        Instr::halt as u8,
+    ][..]);
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
+}
+
+#[test]
+fn test_assemble_operands() {
+    let program = vm::assemble("
+        push_u16 0x1234      // hex
+        push_i8 -128         # signed
+        push_i32 -1
+        push_f32 1.5
+        push_f64 -inf
+        push_u64 0b101
+        push_u32 ptr 300
+        push_u8 ptr_off 1, 2
+        push_u8 idr_ptr 0
+        push_u8 idr_ptr_off 0 70000
+        checked add_u8
+        abs_f64
+    ").unwrap();
+
+    assert_eq!(&program[..], &[
+        Instr::push_u16 as u8, Instr::type_int as u8, Instr::size_16 as u8, 0x12, 0x34,
+        Instr::push_i8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 0x80,
+        Instr::push_i32 as u8, Instr::type_int as u8, Instr::size_32 as u8, 0xff, 0xff, 0xff, 0xff,
+        Instr::push_f32 as u8, Instr::type_int as u8, Instr::size_32 as u8, 0x3f, 0xc0, 0, 0,
+        Instr::push_f64 as u8, Instr::type_int as u8, Instr::size_64 as u8, 0xff, 0xf0, 0, 0, 0, 0, 0, 0,
+        Instr::push_u64 as u8, Instr::type_int as u8, Instr::size_8 as u8, 5,
+        Instr::push_u32 as u8, Instr::type_ptr as u8, Instr::size_16 as u8, 0x01, 0x2c,
+        Instr::push_u8 as u8, Instr::type_ptr_off as u8, Instr::size_8 as u8, 1, Instr::size_8 as u8, 2,
+        Instr::push_u8 as u8, Instr::type_idr_ptr as u8, Instr::size_8 as u8, 0,
+        Instr::push_u8 as u8, Instr::type_idr_ptr_off as u8, Instr::size_8 as u8, 0,
+        Instr::size_32 as u8, 0, 1, 0x11, 0x70,
+        Instr::checked as u8, Instr::add_u8 as u8,
+        Instr::ext as u8, Instr::abs_f64.extended_opcode().unwrap(),
+    ][..]);
+}
+
+#[test]
+fn test_assemble_wide_labels() {
+    let mut source = String::from("jmp end\n");
+    for _ in 0..300 {
+        source.push_str("nop\n");
+    }
+    source.push_str("end: halt\n");
+
+    let program = vm::assemble(&source).unwrap();
+    assert_eq!(&program[..5], &[Instr::jmp as u8, Instr::type_int as u8, Instr::size_16 as u8, 0x01, 0x31][..]);
+    assert_eq!(program[0x131], Instr::halt as u8);
+}
+
+#[test]
+fn test_assemble_errors() {
+    let error = vm::assemble("nop\n  push_u8 256").unwrap_err();
+    assert_eq!(error.get_kind(), AssembleErrorKind::NumberOutOfRange);
+    assert_eq!((error.get_line(), error.get_column()), (2, 11));
+    assert_eq!(error.to_string(), "2:11: number out of range");
+
+    let error = vm::assemble("\n\n    foo_u8").unwrap_err();
+    assert_eq!(error.get_kind(), AssembleErrorKind::UnknownInstr);
+    assert_eq!((error.get_line(), error.get_column()), (3, 5));
+
+    let mut source = String::from("nop\n    push_u8 end\n");
+    for _ in 0..300 {
+        source.push_str("nop\n");
+    }
+    source.push_str("end: halt\n");
+    let error = vm::assemble(&source).unwrap_err();
+    assert_eq!(error.get_kind(), AssembleErrorKind::NumberOutOfRange);
+    assert_eq!((error.get_line(), error.get_column()), (2, 13));
+
+    let error = vm::assemble("jmp nowhere").unwrap_err();
+    assert_eq!(error.get_kind(), AssembleErrorKind::UndefinedLabel);
+    assert_eq!((error.get_line(), error.get_column()), (1, 5));
+
+    let error = vm::assemble("a:\na: nop").unwrap_err();
+    assert_eq!(error.get_kind(), AssembleErrorKind::DuplicateLabel);
+    assert_eq!((error.get_line(), error.get_column()), (2, 1));
+
+    assert_eq!(vm::assemble("push_u8").unwrap_err().get_kind(), AssembleErrorKind::MissingOperand);
+    assert_eq!(vm::assemble("push_u8 ptr_off 1").unwrap_err().get_kind(), AssembleErrorKind::MissingOperand);
+    assert_eq!(vm::assemble("add_u8 1").unwrap_err().get_kind(), AssembleErrorKind::UnexpectedToken);
+    assert_eq!(vm::assemble("push_i8 128").unwrap_err().get_kind(), AssembleErrorKind::NumberOutOfRange);
+    assert_eq!(vm::assemble("push_f32 x").unwrap_err().get_kind(), AssembleErrorKind::InvalidNumber);
+    assert_eq!(vm::assemble("type_int").unwrap_err().get_kind(), AssembleErrorKind::UnknownInstr);
+    assert_eq!(vm::assemble("ext").unwrap_err().get_kind(), AssembleErrorKind::UnknownInstr);
+    assert_eq!(vm::assemble("1abc: nop").unwrap_err().get_kind(), AssembleErrorKind::InvalidLabel);
+}
@@ -0,0 +1,126 @@
+extern crate vm;
+
+
+use vm::{Process, Status, Instr, ProgramBuilder, BuildError, OperandValue};
+
+
+#[test]
+fn test_build_program() {
+    // the PROGRAM of tests/test.rs, counting to 5
+    let mut builder = ProgramBuilder::new();
+    let function = builder.label();
+    let function_end = builder.label();
+    let repeat = builder.label();
+
+    builder.call(function).jmp(function_end);
+    builder.bind(function).push_u8(0);
+    builder.bind(repeat).push_u8(1).add_u8();
+    builder.copy_u8().push_u8(5).neq_u8().if_jmp(repeat);
+    builder.pop_u8().ret();
+    builder.bind(function_end).halt();
+
+    let program = builder.build().unwrap();
+    assert_eq!(&program[..], &vm::assemble("
+        call function
+        jmp function_end
+        function:
+            push_u8 0
+        loop:
+            push_u8 1
+            add_u8
+            copy_u8
+            push_u8 5
+            neq_u8
+            if_jmp loop
+            pop_u8
+            ret
+        function_end:
+            halt
+    ").unwrap()[..]);
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
+}
+
+#[test]
+fn test_build_sizes() {
+    let mut builder = ProgramBuilder::new();
+    builder.push_u32(7).push_i16(-1).push_f32(1.5).push_u64(0x1_0000_0000);
+    builder.with_operand(Instr::push_u8, Instr::type_ptr_off, 300, 2);
+    builder.checked().add_u32();
+
+    assert_eq!(&builder.build().unwrap()[..], &[
+        Instr::push_u32 as u8, Instr::type_int as u8, Instr::size_8 as u8, 7,
+        Instr::push_i16 as u8, Instr::type_int as u8, Instr::size_16 as u8, 0xff, 0xff,
+        Instr::push_f32 as u8, Instr::type_int as u8, Instr::size_32 as u8, 0x3f, 0xc0, 0, 0,
+        Instr::push_u64 as u8, Instr::type_int as u8, Instr::size_64 as u8, 0, 0, 0, 1, 0, 0, 0, 0,
+        Instr::push_u8 as u8, Instr::type_ptr_off as u8, Instr::size_16 as u8, 0x01, 0x2c, Instr::size_8 as u8, 2,
+        Instr::checked as u8, Instr::add_u32 as u8,
+    ][..]);
+}
+
+#[test]
+fn test_build_forward_labels() {
+    let mut builder = ProgramBuilder::new();
+    let end = builder.label();
+
+    builder.jmp(end);
+    for _ in 0..300 {
+        builder.nop();
+    }
+    builder.bind(end).halt();
+
+    let program = builder.build().unwrap();
+    assert_eq!(&program[..5], &[Instr::jmp as u8, Instr::type_int as u8, Instr::size_16 as u8, 0x01, 0x31][..]);
+    assert_eq!(program[0x131], Instr::halt as u8);
+}
+
+#[test]
+fn test_build_errors() {
+    let mut builder = ProgramBuilder::new();
+    let label = builder.label();
+    builder.jmp(label);
+    assert_eq!(builder.build().unwrap_err(), BuildError::UnboundLabel(label));
+
+    builder.bind(label).bind(label);
+    assert_eq!(builder.build().unwrap_err(), BuildError::LabelBoundTwice(label));
+
+    // labels of another builder are rejected instead of indexing past ours
+    let mut other = ProgramBuilder::new();
+    other.label();
+    let foreign = other.label();
+    let mut builder = ProgramBuilder::new();
+    builder.jmp(foreign);
+    assert_eq!(builder.build().unwrap_err(), BuildError::UnknownLabel(foreign));
+    let mut builder = ProgramBuilder::new();
+    builder.bind(foreign).halt();
+    assert_eq!(builder.build().unwrap_err(), BuildError::UnknownLabel(foreign));
+
+    // call_args takes a target and a count, one operand would be malformed
+    let mut builder = ProgramBuilder::new();
+    builder.with_operand(Instr::call_args, Instr::type_int, 0, 0).halt();
+    assert_eq!(builder.build().unwrap_err(), BuildError::OperandCount(Instr::call_args));
+    let mut builder = ProgramBuilder::new();
+    builder.instr(Instr::push_u8);
+    assert_eq!(builder.build().unwrap_err(), BuildError::OperandCount(Instr::push_u8));
+}
+
+#[test]
+fn test_build_operands() {
+    let mut builder = ProgramBuilder::new();
+    let function = builder.label();
+    builder.with_operands(Instr::call_args, &[OperandValue::Label(function), OperandValue::Bits(Instr::type_int, 2, 0)]);
+    builder.bind(function).ret();
+
+    let mut expected = ProgramBuilder::new();
+    let function = expected.label();
+    expected.call_args(function, 2).bind(function).ret();
+    assert_eq!(&builder.build().unwrap()[..], &expected.build().unwrap()[..]);
+}
+
+#[test]
+fn test_build_default() {
+    let mut builder = ProgramBuilder::default();
+    builder.push_u8(1).halt();
+    assert_eq!(&builder.build().unwrap()[..], &vm::assemble("push_u8 1\nhalt").unwrap()[..]);
+}
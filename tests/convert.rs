@@ -0,0 +1,126 @@
+extern crate vm;
+
+
+use std::{f32, f64};
+
+use vm::{Process, Instr};
+
+
+// push_{type} with a full width immediate
+fn push(program: &mut Vec<u8>, instr: Instr, size: Instr, bits: u64, bytes: usize) {
+    program.extend_from_slice(&[instr as u8, Instr::type_int as u8, size as u8]);
+    for i in (0..bytes).rev() {
+        program.push((bits >> (i * 8)) as u8);
+    }
+}
+
+// runs program and pops the bytes wide result, which must be the whole stack
+fn run(program: &[u8], bytes: usize) -> u64 {
+    let mut process = Process::new(program);
+    vm::run(&mut process).unwrap();
+    let value = match bytes {
+        1 => process.pop_u8().unwrap() as u64,
+        2 => process.pop_u16().unwrap() as u64,
+        4 => process.pop_u32().unwrap() as u64,
+        _ => process.pop_u64().unwrap(),
+    };
+    assert!(process.pop_u8().is_err());
+    value
+}
+
+fn from_u8(instr: Instr, value: u8, bytes: usize) -> u64 {
+    let mut program = Vec::new();
+    push(&mut program, Instr::push_u8, Instr::size_8, value as u64, 1);
+    program.push(instr as u8);
+    run(&program, bytes)
+}
+fn from_u16(instr: Instr, value: u16, bytes: usize) -> u64 {
+    let mut program = Vec::new();
+    push(&mut program, Instr::push_u16, Instr::size_16, value as u64, 2);
+    program.push(instr as u8);
+    run(&program, bytes)
+}
+fn from_u32(instr: Instr, value: u32, bytes: usize) -> u64 {
+    let mut program = Vec::new();
+    push(&mut program, Instr::push_u32, Instr::size_32, value as u64, 4);
+    program.push(instr as u8);
+    run(&program, bytes)
+}
+fn from_u64(instr: Instr, value: u64, bytes: usize) -> u64 {
+    let mut program = Vec::new();
+    push(&mut program, Instr::push_u64, Instr::size_64, value, 8);
+    program.push(instr as u8);
+    run(&program, bytes)
+}
+
+
+#[test]
+fn test_extend() {
+    assert_eq!(from_u8(Instr::ext_u8, 0xff, 8), 0xff);
+    assert_eq!(from_u16(Instr::ext_u16, 0x8000, 8), 0x8000);
+    assert_eq!(from_u32(Instr::ext_u32, 0xffff_ffff, 8), 0xffff_ffff);
+
+    assert_eq!(from_u8(Instr::ext_i8, -1i8 as u8, 8), -1i64 as u64);
+    assert_eq!(from_u8(Instr::ext_i8, 0x7f, 8), 0x7f);
+    assert_eq!(from_u16(Instr::ext_i16, -300i16 as u16, 8), -300i64 as u64);
+    assert_eq!(from_u32(Instr::ext_i32, i32::MIN as u32, 8), i32::MIN as i64 as u64);
+}
+
+#[test]
+fn test_wrap() {
+    assert_eq!(from_u64(Instr::wrap_u8, 0x1234, 1), 0x34);
+    assert_eq!(from_u64(Instr::wrap_u16, -2i64 as u64, 2), -2i16 as u16 as u64);
+    assert_eq!(from_u64(Instr::wrap_u32, 0x1_0000_0005, 4), 5);
+}
+
+#[test]
+fn test_int_to_float() {
+    let f32_of = |bits: u64| f32::from_bits(bits as u32);
+
+    assert_eq!(f32_of(from_u64(Instr::cvt_i64_f32, -3i64 as u64, 4)), -3.0);
+    assert_eq!(f32_of(from_u64(Instr::cvt_u64_f32, u64::MAX, 4)), 18446744073709551616.0);
+    assert_eq!(f32_of(from_u64(Instr::cvt_u64_f32, 16777217, 4)), 16777216.0);
+    assert_eq!(f64::from_bits(from_u64(Instr::cvt_i64_f64, i64::MIN as u64, 8)), -9223372036854775808.0);
+    assert_eq!(f64::from_bits(from_u64(Instr::cvt_u64_f64, (1 << 53) + 1, 8)), 9007199254740992.0);
+}
+
+#[test]
+fn test_float_to_int() {
+    assert_eq!(from_u32(Instr::cvt_f32_i64, (-2.9f32).to_bits(), 8), -2i64 as u64);
+    assert_eq!(from_u32(Instr::cvt_f32_i64, f32::INFINITY.to_bits(), 8), i64::MAX as u64);
+    assert_eq!(from_u32(Instr::cvt_f32_i64, f32::NAN.to_bits(), 8), 0);
+    assert_eq!(from_u32(Instr::cvt_f32_u64, (-1.0f32).to_bits(), 8), 0);
+    assert_eq!(from_u32(Instr::cvt_f32_u64, 1e20f32.to_bits(), 8), u64::MAX);
+
+    assert_eq!(from_u64(Instr::cvt_f64_i64, 1e300f64.to_bits(), 8), i64::MAX as u64);
+    assert_eq!(from_u64(Instr::cvt_f64_i64, (-1e300f64).to_bits(), 8), i64::MIN as u64);
+    assert_eq!(from_u64(Instr::cvt_f64_i64, f64::NAN.to_bits(), 8), 0);
+    assert_eq!(from_u64(Instr::cvt_f64_u64, 4294967296.75f64.to_bits(), 8), 4294967296);
+    assert_eq!(from_u64(Instr::cvt_f64_u64, f64::NEG_INFINITY.to_bits(), 8), 0);
+}
+
+#[test]
+fn test_promote_demote() {
+    assert_eq!(from_u32(Instr::promote_f32, 0.1f32.to_bits(), 8), (0.1f32 as f64).to_bits());
+    assert_eq!(from_u32(Instr::promote_f32, (-0.0f32).to_bits(), 8), (-0.0f64).to_bits());
+    assert_eq!(from_u64(Instr::demote_f64, 0.1f64.to_bits(), 4), 0.1f32.to_bits() as u64);
+    assert_eq!(from_u64(Instr::demote_f64, 1e300f64.to_bits(), 4), f32::INFINITY.to_bits() as u64);
+    assert!(f32::from_bits(from_u64(Instr::demote_f64, f64::NAN.to_bits(), 4) as u32).is_nan());
+}
+
+#[test]
+fn test_composed_conversions() {
+    // i32 -> f64
+    let mut program = Vec::new();
+    push(&mut program, Instr::push_i32, Instr::size_32, -7i32 as u32 as u64, 4);
+    program.push(Instr::ext_i32 as u8);
+    program.push(Instr::cvt_i64_f64 as u8);
+    assert_eq!(f64::from_bits(run(&program, 8)), -7.0);
+
+    // f64 -> u8
+    let mut program = Vec::new();
+    push(&mut program, Instr::push_f64, Instr::size_64, 200.5f64.to_bits(), 8);
+    program.push(Instr::cvt_f64_u64 as u8);
+    program.push(Instr::wrap_u8 as u8);
+    assert_eq!(run(&program, 1), 200);
+}
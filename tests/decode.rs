@@ -0,0 +1,30 @@
+extern crate vm;
+
+
+use std::convert::TryFrom;
+
+use vm::{Process, Instr, InvalidOpcode, TrapKind};
+
+
+#[test]
+fn test_decode_opcodes() {
+    for opcode in 0..256usize {
+        match Instr::try_from(opcode as u8) {
+            Ok(instr) => assert_eq!(instr as usize, opcode),
+            Err(error) => {
+                assert!(opcode >= Instr::count());
+                assert_eq!(error, InvalidOpcode(opcode as u8));
+            },
+        }
+    }
+
+    let program = [Instr::nop as u8, Instr::nop as u8, 255];
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::InvalidInstr(255));
+    assert_eq!(trap.get_program_counter(), 2);
+
+    let program = [Instr::push_u8 as u8, 255, Instr::size_8 as u8, 0];
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).unwrap_err().get_kind(), TrapKind::InvalidType(255));
+}
@@ -0,0 +1,126 @@
+extern crate vm;
+
+
+use vm::{Instr, DecodeErrorKind};
+
+
+static SOURCE: &'static str = "
+call function
+jmp function_end
+
+function:
+    push_u8 0
+    loop:
+        push_u8 1
+        checked add_u8
+        copy_u8
+        push_u8 5
+        neq_u8
+        if_jmp loop
+    pop_u8
+    ret
+function_end:
+
+push_i16 -300
+push_f64 -2.5
+push_u32 ptr 300
+push_u8 ptr_off 1, 2
+push_u8 idr_ptr_off 0, 70000
+max_f32
+halt
+";
+
+
+fn disassemble(program: &[u8]) -> String {
+    let mut listing = String::new();
+    vm::disassemble(program, &mut listing).unwrap();
+    listing
+}
+
+
+#[test]
+fn test_disassemble_listing() {
+    let program = vm::assemble("
+        jmp end
+        push_i8 -1
+        end: halt
+    ").unwrap();
+
+    assert_eq!(disassemble(&program), "    \
+        jmp label_8 // 0\n    \
+        push_i8 -1 // 4\n\
+        label_8:\n    \
+        halt // 8\n\
+    ");
+}
+
+#[test]
+fn test_disassemble_round_trip() {
+    let program = vm::assemble(SOURCE).unwrap();
+    let listing = disassemble(&program);
+
+    assert_eq!(&vm::assemble(&listing).unwrap()[..], &program[..]);
+    assert_eq!(disassemble(&vm::assemble(&listing).unwrap()), listing);
+}
+
+#[test]
+fn test_disassemble_float_bits() {
+    let mut program = Vec::new();
+    for bits in [0xffc0_0123, (-0.0f32).to_bits(), f32::INFINITY.to_bits()].iter() {
+        program.extend_from_slice(&[Instr::push_f32 as u8, Instr::type_int as u8, Instr::size_32 as u8]);
+        program.extend_from_slice(&bits.to_be_bytes());
+    }
+    for bits in [0x7ff0_0000_0000_0042, (-0.0f64).to_bits(), (-1.5f64).to_bits()].iter() {
+        program.extend_from_slice(&[Instr::push_f64 as u8, Instr::type_int as u8, Instr::size_64 as u8]);
+        program.extend_from_slice(&bits.to_be_bytes());
+    }
+    let listing = disassemble(&program);
+
+    assert!(listing.contains("push_f32 0xffc00123 "));
+    assert!(listing.contains("push_f64 0x7ff0000000000042 "));
+    assert!(listing.contains("push_f64 -0 "));
+    assert_eq!(&vm::assemble(&listing).unwrap()[..], &program[..]);
+
+    assert_eq!(&vm::assemble("push_f32 0x3fc00000").unwrap()[..], &vm::assemble("push_f32 1.5").unwrap()[..]);
+    assert!(vm::assemble("push_f32 0x100000000").is_err());
+}
+
+#[test]
+fn test_disassemble_operands() {
+    let program = [
+        Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_16 as u8, 0x01, 0x80,
+        Instr::push_f32 as u8, Instr::type_int as u8, Instr::size_32 as u8, 0x3f, 0xc0, 0, 0,
+        // target inside the push_u8 operand
+        Instr::jmp as u8, Instr::type_int as u8, Instr::size_8 as u8, 3,
+        Instr::jmp as u8, Instr::type_ptr as u8, Instr::size_8 as u8, 9,
+    ];
+
+    assert_eq!(disassemble(&program), "    \
+        push_u8 128 // 0\n    \
+        push_f32 1.5 // 5\n    \
+        jmp 3 // 12\n    \
+        jmp ptr 9 // 16\n\
+    ");
+}
+
+#[test]
+fn test_disassemble_invalid() {
+    let program = [
+        Instr::size_8 as u8,
+        Instr::nop as u8,
+        Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_16 as u8, 0,
+    ];
+
+    assert_eq!(disassemble(&program), "    \
+        // 0: invalid instruction 11\n    \
+        nop // 1\n    \
+        // 2: unexpected end of program\n    \
+        // 3: invalid instruction 6\n    \
+        // 4: invalid instruction 12\n    \
+        nop // 5\n\
+    ");
+
+    let error = vm::decode(&program, 2).unwrap_err();
+    assert_eq!(error.get_kind(), DecodeErrorKind::Truncated);
+    assert_eq!(error.get_offset(), 6);
+}
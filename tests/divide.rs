@@ -0,0 +1,49 @@
+extern crate vm;
+
+
+use vm::{Process, Status, Instr, TrapKind, DivMode};
+
+
+#[test]
+fn test_divide() {
+    // i32::MIN / -1
+    let program = [
+        Instr::push_i32 as u8, Instr::type_int as u8, Instr::size_32 as u8, 0x80, 0, 0, 0,
+        Instr::push_i32 as u8, Instr::type_int as u8, Instr::size_32 as u8, 0xff, 0xff, 0xff, 0xff,
+        Instr::div_i32 as u8,
+    ];
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::Overflow);
+    assert_eq!(trap.get_program_counter(), 14);
+
+    let mut process = Process::new(&program);
+    process.set_div_mode(DivMode::Wrap);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u32(), Ok(0x8000_0000));
+
+    let program = [
+        Instr::push_i32 as u8, Instr::type_int as u8, Instr::size_32 as u8, 0x80, 0, 0, 0,
+        Instr::push_i32 as u8, Instr::type_int as u8, Instr::size_32 as u8, 0xff, 0xff, 0xff, 0xff,
+        Instr::rem_i32 as u8,
+    ];
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u32(), Ok(0));
+
+    let program = [
+        Instr::push_u16 as u8, Instr::type_int as u8, Instr::size_8 as u8, 7,
+        Instr::push_u16 as u8, Instr::type_int as u8, Instr::size_8 as u8, 0,
+        Instr::rem_u16 as u8,
+    ];
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::DivideByZero);
+    assert_eq!(trap.get_program_counter(), 8);
+
+    let mut process = Process::new(&program);
+    process.set_div_mode(DivMode::Wrap);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u16(), Ok(0));
+    assert!(process.pop_u8().is_err());
+}
@@ -0,0 +1,27 @@
+extern crate vm;
+
+
+use std::convert::TryFrom;
+
+use vm::{Process, Instr, TrapKind};
+
+
+#[test]
+fn test_extended_opcodes() {
+    assert_eq!(Instr::try_from(Instr::ext as u8), Ok(Instr::ext));
+    assert_eq!(Instr::abs_f32.opcode(), Instr::ext as u8);
+    assert_eq!(Instr::abs_f32.extended_opcode(), Some(0));
+    assert_eq!(Instr::from_extended(0), Ok(Instr::abs_f32));
+    assert_eq!(Instr::add_u8.extended_opcode(), None);
+    assert!(Instr::from_extended(Instr::extended_count() as u8).is_err());
+
+    let program = [Instr::nop as u8, Instr::ext as u8, 200];
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::InvalidInstr(200));
+    assert_eq!(trap.get_program_counter(), 1);
+
+    let program = [Instr::ext as u8];
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).unwrap_err().get_kind(), TrapKind::TruncatedProgram);
+}
@@ -0,0 +1,249 @@
+extern crate vm;
+
+
+use std::{f32, f64};
+
+use vm::{Process, Instr};
+
+
+fn push_f32(program: &mut Vec<u8>, value: f32) {
+    let bits = value.to_bits();
+    program.extend_from_slice(&[Instr::push_f32 as u8, Instr::type_int as u8, Instr::size_32 as u8]);
+    program.extend_from_slice(&[(bits >> 24) as u8, (bits >> 16) as u8, (bits >> 8) as u8, bits as u8]);
+}
+fn push_f64(program: &mut Vec<u8>, value: f64) {
+    let bits = value.to_bits();
+    program.extend_from_slice(&[Instr::push_f64 as u8, Instr::type_int as u8, Instr::size_64 as u8]);
+    for i in 0..8 {
+        program.push((bits >> (56 - i * 8)) as u8);
+    }
+}
+
+// encodes instr, extended ones included
+fn emit(program: &mut Vec<u8>, instr: Instr) {
+    program.push(instr.opcode());
+    program.extend(instr.extended_opcode());
+}
+
+fn run<'a>(program: &'a [u8]) -> Process<'a> {
+    let mut process = Process::with_memory(program, 8);
+    vm::run(&mut process).unwrap();
+    process
+}
+
+fn unary_f32(instr: Instr, a: f32) -> f32 {
+    let mut program = Vec::new();
+    push_f32(&mut program, a);
+    emit(&mut program, instr);
+    run(&program).pop_f32().unwrap()
+}
+fn unary_f64(instr: Instr, a: f64) -> f64 {
+    let mut program = Vec::new();
+    push_f64(&mut program, a);
+    emit(&mut program, instr);
+    run(&program).pop_f64().unwrap()
+}
+
+fn binary_f32(instr: Instr, a: f32, b: f32) -> f32 {
+    let mut program = Vec::new();
+    push_f32(&mut program, a);
+    push_f32(&mut program, b);
+    emit(&mut program, instr);
+    run(&program).pop_f32().unwrap()
+}
+fn binary_f64(instr: Instr, a: f64, b: f64) -> f64 {
+    let mut program = Vec::new();
+    push_f64(&mut program, a);
+    push_f64(&mut program, b);
+    emit(&mut program, instr);
+    run(&program).pop_f64().unwrap()
+}
+
+fn compare_f32(instr: Instr, a: f32, b: f32) -> bool {
+    let mut program = Vec::new();
+    push_f32(&mut program, a);
+    push_f32(&mut program, b);
+    emit(&mut program, instr);
+    run(&program).pop_u8().unwrap() != 0
+}
+fn compare_f64(instr: Instr, a: f64, b: f64) -> bool {
+    let mut program = Vec::new();
+    push_f64(&mut program, a);
+    push_f64(&mut program, b);
+    emit(&mut program, instr);
+    run(&program).pop_u8().unwrap() != 0
+}
+
+
+#[test]
+fn test_f32_push_pop_copy() {
+    for &value in &[1.5f32, -0.0, f32::NAN, f32::INFINITY, f32::MIN_POSITIVE / 2.0] {
+        let mut program = Vec::new();
+        push_f32(&mut program, value);
+        push_f32(&mut program, 7.0);
+        program.push(Instr::pop_f32 as u8);
+        program.push(Instr::copy_f32 as u8);
+        let mut process = run(&program);
+        assert_eq!(process.pop_f32().unwrap().to_bits(), value.to_bits());
+        assert_eq!(process.pop_f32().unwrap().to_bits(), value.to_bits());
+    }
+}
+#[test]
+fn test_f64_push_pop_copy() {
+    for &value in &[1.5f64, -0.0, f64::NAN, f64::NEG_INFINITY, f64::MIN_POSITIVE / 2.0] {
+        let mut program = Vec::new();
+        push_f64(&mut program, value);
+        push_f64(&mut program, 7.0);
+        program.push(Instr::pop_f64 as u8);
+        program.push(Instr::copy_f64 as u8);
+        let mut process = run(&program);
+        assert_eq!(process.pop_f64().unwrap().to_bits(), value.to_bits());
+        assert_eq!(process.pop_f64().unwrap().to_bits(), value.to_bits());
+    }
+}
+
+#[test]
+fn test_f32_load_save() {
+    let value = f32::from_bits(0x7fc0_1234);
+    let mut program = Vec::new();
+    push_f32(&mut program, value);
+    program.extend_from_slice(&[Instr::push_u64 as u8, Instr::type_int as u8, Instr::size_8 as u8, 4]);
+    program.push(Instr::save_f32 as u8);
+    program.extend_from_slice(&[Instr::push_u64 as u8, Instr::type_int as u8, Instr::size_8 as u8, 4]);
+    program.push(Instr::load_f32 as u8);
+    let mut process = run(&program);
+    assert_eq!(process.pop_f32().unwrap().to_bits(), 0x7fc0_1234);
+    assert_eq!(&process.get_memory()[4..], &[0x7f, 0xc0, 0x12, 0x34]);
+}
+#[test]
+fn test_f64_load_save() {
+    let value = -0.0f64;
+    let mut program = Vec::new();
+    push_f64(&mut program, value);
+    program.extend_from_slice(&[Instr::push_u64 as u8, Instr::type_int as u8, Instr::size_8 as u8, 0]);
+    program.push(Instr::save_f64 as u8);
+    program.extend_from_slice(&[Instr::push_u64 as u8, Instr::type_int as u8, Instr::size_8 as u8, 0]);
+    program.push(Instr::load_f64 as u8);
+    let mut process = run(&program);
+    assert_eq!(process.pop_f64().unwrap().to_bits(), value.to_bits());
+    assert_eq!(process.get_memory(), &[0x80, 0, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn test_f32_arithmetic() {
+    assert_eq!(binary_f32(Instr::add_f32, 0.1, 0.2), 0.1f32 + 0.2);
+    assert_eq!(binary_f32(Instr::add_f32, 16777216.0, 1.0), 16777216.0);
+    assert_eq!(binary_f32(Instr::add_f32, f32::MAX, f32::MAX), f32::INFINITY);
+    assert!(binary_f32(Instr::add_f32, f32::INFINITY, f32::NEG_INFINITY).is_nan());
+    assert_eq!(binary_f32(Instr::add_f32, -0.0, -0.0).to_bits(), (-0.0f32).to_bits());
+    assert_eq!(binary_f32(Instr::add_f32, -0.0, 0.0).to_bits(), 0.0f32.to_bits());
+
+    assert_eq!(binary_f32(Instr::sub_f32, 1.0, 0.75), 0.25);
+    assert_eq!(binary_f32(Instr::sub_f32, 0.0, 0.0).to_bits(), 0.0f32.to_bits());
+    assert!(binary_f32(Instr::sub_f32, f32::INFINITY, f32::INFINITY).is_nan());
+
+    assert_eq!(binary_f32(Instr::mul_f32, 1.5, -2.0), -3.0);
+    assert_eq!(binary_f32(Instr::mul_f32, -0.0, 5.0).to_bits(), (-0.0f32).to_bits());
+    assert!(binary_f32(Instr::mul_f32, 0.0, f32::INFINITY).is_nan());
+
+    assert_eq!(binary_f32(Instr::div_f32, 1.0, 3.0), 1.0f32 / 3.0);
+    assert_eq!(binary_f32(Instr::div_f32, 1.0, 0.0), f32::INFINITY);
+    assert_eq!(binary_f32(Instr::div_f32, 1.0, -0.0), f32::NEG_INFINITY);
+    assert!(binary_f32(Instr::div_f32, 0.0, 0.0).is_nan());
+
+    assert_eq!(binary_f32(Instr::rem_f32, 5.5, 2.0), 1.5);
+    assert_eq!(binary_f32(Instr::rem_f32, -5.5, 2.0), -1.5);
+    assert!(binary_f32(Instr::rem_f32, 1.0, 0.0).is_nan());
+
+    assert_eq!(unary_f32(Instr::neg_f32, 2.5), -2.5);
+    assert_eq!(unary_f32(Instr::neg_f32, 0.0).to_bits(), (-0.0f32).to_bits());
+    assert_eq!(unary_f32(Instr::neg_f32, f32::INFINITY), f32::NEG_INFINITY);
+    assert!(unary_f32(Instr::neg_f32, f32::NAN).is_nan());
+}
+#[test]
+fn test_f64_arithmetic() {
+    assert_eq!(binary_f64(Instr::add_f64, 0.1, 0.2), 0.30000000000000004);
+    assert_eq!(binary_f64(Instr::add_f64, 9007199254740992.0, 1.0), 9007199254740992.0);
+    assert_eq!(binary_f64(Instr::add_f64, f64::MAX, f64::MAX), f64::INFINITY);
+    assert!(binary_f64(Instr::add_f64, f64::INFINITY, f64::NEG_INFINITY).is_nan());
+    assert_eq!(binary_f64(Instr::add_f64, -0.0, -0.0).to_bits(), (-0.0f64).to_bits());
+
+    assert_eq!(binary_f64(Instr::sub_f64, 1.0, 0.75), 0.25);
+    assert!(binary_f64(Instr::sub_f64, f64::NEG_INFINITY, f64::NEG_INFINITY).is_nan());
+
+    assert_eq!(binary_f64(Instr::mul_f64, 1.5, -2.0), -3.0);
+    assert_eq!(binary_f64(Instr::mul_f64, f64::MIN_POSITIVE, 0.5), f64::MIN_POSITIVE / 2.0);
+    assert!(binary_f64(Instr::mul_f64, f64::NAN, 1.0).is_nan());
+
+    assert_eq!(binary_f64(Instr::div_f64, 1.0, 3.0), 1.0f64 / 3.0);
+    assert_eq!(binary_f64(Instr::div_f64, -1.0, 0.0), f64::NEG_INFINITY);
+    assert!(binary_f64(Instr::div_f64, f64::INFINITY, f64::INFINITY).is_nan());
+
+    assert_eq!(binary_f64(Instr::rem_f64, 5.5, -2.0), 1.5);
+    assert_eq!(binary_f64(Instr::rem_f64, -0.0, 1.0).to_bits(), (-0.0f64).to_bits());
+    assert_eq!(binary_f64(Instr::rem_f64, 3.0, f64::INFINITY), 3.0);
+    assert!(binary_f64(Instr::rem_f64, f64::INFINITY, 1.0).is_nan());
+
+    assert_eq!(unary_f64(Instr::neg_f64, 2.5), -2.5);
+    assert_eq!(unary_f64(Instr::neg_f64, 0.0).to_bits(), (-0.0f64).to_bits());
+    assert!(unary_f64(Instr::neg_f64, f64::NAN).is_nan());
+}
+
+#[test]
+fn test_f32_compare() {
+    assert!(compare_f32(Instr::lt_f32, -1.5, 1.0));
+    assert!(!compare_f32(Instr::lt_f32, 1.0, f32::NAN));
+    assert!(compare_f32(Instr::lt_f32, f32::NEG_INFINITY, f32::MIN));
+    assert!(compare_f32(Instr::lte_f32, -0.0, 0.0));
+    assert!(!compare_f32(Instr::lte_f32, f32::NAN, f32::NAN));
+    assert!(compare_f32(Instr::gt_f32, f32::INFINITY, f32::MAX));
+    assert!(!compare_f32(Instr::gt_f32, f32::NAN, 0.0));
+    assert!(compare_f32(Instr::gte_f32, 0.0, -0.0));
+    assert!(!compare_f32(Instr::gte_f32, 0.0, f32::NAN));
+    assert!(compare_f32(Instr::eq_f32, 0.0, -0.0));
+    assert!(!compare_f32(Instr::eq_f32, f32::NAN, f32::NAN));
+    assert!(!compare_f32(Instr::neq_f32, -0.0, 0.0));
+    assert!(compare_f32(Instr::neq_f32, f32::NAN, f32::NAN));
+}
+#[test]
+fn test_f64_compare() {
+    assert!(compare_f64(Instr::lt_f64, -1.5, 1.0));
+    assert!(!compare_f64(Instr::lt_f64, f64::NAN, 1.0));
+    assert!(compare_f64(Instr::lte_f64, -0.0, 0.0));
+    assert!(!compare_f64(Instr::lte_f64, 1.0, f64::NAN));
+    assert!(compare_f64(Instr::gt_f64, 0.5, 0.25));
+    assert!(!compare_f64(Instr::gt_f64, f64::NAN, f64::NEG_INFINITY));
+    assert!(compare_f64(Instr::gte_f64, f64::INFINITY, f64::INFINITY));
+    assert!(!compare_f64(Instr::gte_f64, f64::NAN, 0.0));
+    assert!(compare_f64(Instr::eq_f64, -0.0, 0.0));
+    assert!(!compare_f64(Instr::eq_f64, f64::NAN, f64::NAN));
+    assert!(!compare_f64(Instr::neq_f64, 0.1 + 0.2, 0.30000000000000004));
+    assert!(compare_f64(Instr::neq_f64, f64::NAN, 1.0));
+}
+
+#[test]
+fn test_rem_fmod() {
+    // truncated remainder with the sign of the dividend, never traps
+    assert_eq!(binary_f32(Instr::rem_f32, 7.0, -3.0), 1.0);
+    assert_eq!(binary_f32(Instr::rem_f32, -7.0, 3.0), -1.0);
+    assert_eq!(binary_f32(Instr::rem_f32, -0.0, 3.0).to_bits(), (-0.0f32).to_bits());
+    assert_eq!(binary_f32(Instr::rem_f32, 1.0, f32::NEG_INFINITY), 1.0);
+    assert!(binary_f32(Instr::rem_f32, f32::NAN, 1.0).is_nan());
+
+    assert_eq!(binary_f64(Instr::rem_f64, 0.3, 0.1), 0.3f64 % 0.1);
+    assert_eq!(binary_f64(Instr::rem_f64, -4.0, 2.0).to_bits(), (-0.0f64).to_bits());
+    assert!(binary_f64(Instr::rem_f64, 1.0, -0.0).is_nan());
+}
+
+#[test]
+fn test_float_math() {
+    assert_eq!(unary_f32(Instr::abs_f32, -1.5), 1.5);
+    assert_eq!(unary_f32(Instr::abs_f32, -0.0).to_bits(), 0);
+    assert!(unary_f32(Instr::abs_f32, f32::NAN).is_nan());
+    assert_eq!(unary_f64(Instr::abs_f64, f64::NEG_INFINITY), f64::INFINITY);
+
+    assert_eq!(binary_f32(Instr::min_f32, 1.0, -2.0), -2.0);
+    assert_eq!(binary_f32(Instr::max_f32, f32::NAN, 3.0), 3.0);
+    assert_eq!(binary_f64(Instr::min_f64, f64::NAN, 3.0), 3.0);
+    assert_eq!(binary_f64(Instr::max_f64, 1.0, 2.0), 2.0);
+}
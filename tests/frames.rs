@@ -0,0 +1,131 @@
+extern crate vm;
+
+
+use vm::{Process, Status, ProgramBuilder, Trap, TrapKind, ValueType};
+
+
+const SOURCE: &'static str = "
+    push_u8 7
+    push_u16 300
+    push_u16 12
+    call_args add_scaled, 4
+    jmp end
+add_scaled:
+    enter 2
+    local_get_u16 0
+    local_get_u16 2
+    add_u16
+    local_set_u16 4
+    local_get_u16 4
+    push_u16 2
+    mul_u16
+    ret
+end:
+    nop
+";
+
+
+#[test]
+fn test_frame_call() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.get_call_depth(), 0);
+    assert_eq!(process.get_frame_pointer(), 0);
+
+    // the arguments and the local are gone, the result is left in their place
+    assert_eq!(process.pop_u16(), Ok(624));
+    assert_eq!(process.pop_u8(), Ok(7));
+    assert_eq!(process.pop_u8().map_err(|trap| trap.get_kind()), Err(TrapKind::StackUnderflow));
+}
+
+#[test]
+fn test_frame_nested() {
+    let program = vm::assemble("
+        push_u8 1
+        call_args outer, 1
+        jmp end
+    outer:
+        enter 1
+        local_get_u8 0
+        call_args inner, 1
+        local_set_u8 1
+        local_get_u8 1
+        ret
+    inner:
+        local_get_u8 0
+        push_u8 10
+        add_u8
+        ret
+    end:
+        nop
+    ").unwrap();
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(11));
+    assert_eq!(process.pop_u8().map_err(|trap| trap.get_kind()), Err(TrapKind::StackUnderflow));
+}
+
+#[test]
+fn test_frame_traps() {
+    // past the local
+    let program = vm::assemble("
+        push_u16 1
+        call_args function, 2
+    function:
+        enter 2
+        local_get_u16 3
+    ").unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Err(Trap::new(TrapKind::InvalidLocal(3), 17)));
+
+    // more arguments than the stack holds
+    let program = vm::assemble("
+        push_u8 1
+        call_args function, 2
+    function:
+        ret
+    ").unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Err(Trap::new(TrapKind::StackUnderflow, 4)));
+
+    // popped into its own locals
+    let program = vm::assemble("
+        call function
+    function:
+        enter 1
+        pop_u8
+        ret
+    ").unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).map_err(|trap| trap.get_kind()), Err(TrapKind::StackUnderflow));
+}
+
+#[test]
+fn test_frame_tooling() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    let mut listing = String::new();
+    vm::disassemble(&program, &mut listing).unwrap();
+    assert!(listing.contains("    call_args label_25, 4 // 13\n"));
+    assert!(listing.contains("    enter 2 // 25\n"));
+    assert_eq!(&vm::assemble(&listing).unwrap()[..], &program[..]);
+
+    assert!(vm::verify(&program).is_ok());
+    let analysis = vm::analyze(&program);
+    assert!(analysis.is_ok());
+    assert_eq!(analysis.get_shape(21), Some(&[ValueType::U8, ValueType::U16][..]));
+    assert_eq!(analysis.get_max_depth(), Some(11));
+
+    let mut builder = ProgramBuilder::new();
+    let add_scaled = builder.label();
+    let end = builder.label();
+    builder.push_u8(7).push_u16(300).push_u16(12).call_args(add_scaled, 4).jmp(end);
+    builder.bind(add_scaled).enter(2);
+    builder.local_get_u16(0).local_get_u16(2).add_u16().local_set_u16(4);
+    builder.local_get_u16(4).push_u16(2).mul_u16().ret();
+    builder.bind(end).nop();
+    assert_eq!(&builder.build().unwrap()[..], &program[..]);
+}
@@ -0,0 +1,92 @@
+extern crate vm;
+
+
+use vm::{Process, Status, State, Instr, CostTable, Trap, TrapKind};
+
+
+#[test]
+fn test_cost_table() {
+    let mut costs = CostTable::new();
+    assert_eq!(costs.instr_cost(Instr::add_u8), 1);
+    assert_eq!(costs.instr_cost(Instr::push_u8), 2);
+    assert_eq!(costs.instr_cost(Instr::push_f64), 9);
+    assert_eq!(costs.instr_cost(Instr::load_u32), 5);
+    assert_eq!(costs.instr_cost(Instr::save_i16), 3);
+    assert_eq!(costs.instr_cost(Instr::pop_u64), 1);
+
+    costs.set_cost(Instr::load_u32, 10).set_byte_cost(3);
+    assert_eq!(costs.get_cost(Instr::load_u32), 10);
+    assert_eq!(costs.get_byte_cost(), 3);
+    assert_eq!(costs.instr_cost(Instr::load_u32), 22);
+    assert_eq!(costs.instr_cost(Instr::push_u16), 7);
+
+    costs.set_cost(Instr::div_u64, u64::MAX);
+    assert_eq!(costs.instr_cost(Instr::div_u64), u64::MAX);
+
+    let costs = CostTable::default();
+    assert_eq!(costs.instr_cost(Instr::load_u32), 5);
+}
+
+#[test]
+fn test_out_of_fuel() {
+    let program = vm::assemble("
+        push_u8 1
+        push_u8 2
+        add_u8
+        push_u64 3
+    ").unwrap();
+    let costs = CostTable::new();
+
+    let mut process = Process::new(&program);
+    process.set_cost_table(costs.clone());
+    process.set_fuel(14);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.get_fuel(), 0);
+
+    // push_u64 is not paid for, it runs once refueled
+    let mut process = Process::new(&program);
+    process.set_cost_table(costs);
+    assert_eq!(process.refuel(5), 5);
+    assert_eq!(vm::run(&mut process), Err(Trap::new(TrapKind::OutOfFuel, 9)));
+    assert_eq!(process.get_state(), State::Running);
+    assert_eq!(process.get_program_counter(), 9);
+    assert_eq!(process.get_fuel(), 0);
+    assert_eq!(process.peek_u8(0), Ok(3));
+
+    assert_eq!(vm::run(&mut process), Err(Trap::new(TrapKind::OutOfFuel, 9)));
+    assert_eq!(process.refuel(10), 10);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.get_fuel(), 1);
+    assert_eq!(process.pop_u64(), Ok(3));
+    assert_eq!(process.pop_u8(), Ok(3));
+
+    // unmetered processes ignore their fuel
+    let mut process = Process::new(&program);
+    assert!(process.get_cost_table().is_none());
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+}
+
+#[test]
+fn test_fuel_prefixes_and_extended() {
+    let mut costs = CostTable::new();
+    costs.set_cost(Instr::checked, 3).set_cost(Instr::swap_u8, 5);
+
+    // checked is paid for together with add_u8
+    let program = vm::assemble("push_u8 1\npush_u8 2\nchecked add_u8").unwrap();
+    let mut process = Process::new(&program);
+    process.set_cost_table(costs.clone());
+    process.set_fuel(7);
+    assert_eq!(vm::run(&mut process), Err(Trap::new(TrapKind::OutOfFuel, 8)));
+    process.refuel(1);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(3));
+
+    let program = vm::assemble("push_u8 1\npush_u8 2\nswap_u8").unwrap();
+    let mut process = Process::new(&program);
+    process.set_cost_table(costs);
+    process.set_fuel(8);
+    assert_eq!(vm::run_for(&mut process, 10), Err(Trap::new(TrapKind::OutOfFuel, 8)));
+    process.clear_cost_table();
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(1));
+}
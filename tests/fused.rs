@@ -0,0 +1,158 @@
+extern crate vm;
+
+
+use vm::{Process, Status, ProgramBuilder, Encoding, OptimizeErrorKind};
+
+
+const SOURCE: &'static str = "
+    push_u8 0
+loop:
+    push_u8 1
+    add_u8
+    copy_u8
+    push_u8 5
+    lt_u8
+    if_jmp loop
+    copy_u8
+    push_u8 0
+    neq_u8
+    if_jmp done
+    pop_u8
+    push_u8 99
+done:
+    nop
+";
+
+
+fn disassemble(program: &[u8]) -> String {
+    let mut listing = String::new();
+    vm::disassemble(program, &mut listing).unwrap();
+    listing
+}
+
+fn taken(source: &str) -> bool {
+    let program = vm::assemble(&format!("{} taken\npush_u8 0\njmp end\ntaken:\npush_u8 1\nend:\nnop", source)).unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished), "{}", source);
+    process.pop_u8().unwrap() == 1
+}
+
+
+#[test]
+fn test_fused_branches() {
+    assert!(taken("push_i16 -3\npush_i16 -3\nbr_eq_i16"));
+    assert!(!taken("push_u64 3\npush_u64 4\nbr_eq_u64"));
+    assert!(taken("push_i8 -1\npush_i8 1\nbr_lt_i8"));
+    assert!(!taken("push_u8 255\npush_u8 1\nbr_lt_u8"));
+    assert!(!taken("push_f32 0.0\npush_f32 0.0\ndiv_f32\npush_f32 1.0\nbr_lt_f32"));
+    assert!(taken("push_f64 -0.0\nbr_zero_f64"));
+    assert!(taken("push_u32 0x10000\nbr_nonzero_u32"));
+    assert!(!taken("push_i32 0\nbr_nonzero_i32"));
+}
+
+#[test]
+fn test_optimize() {
+    let program = vm::assemble(SOURCE).unwrap();
+    let optimized = vm::optimize(&program).unwrap();
+    assert!(optimized.len() < program.len());
+
+    let listing = disassemble(&optimized);
+    assert!(listing.contains("    br_lt_u8 label_4 // 14\n"));
+    assert!(listing.contains("    br_nonzero_u8 label_30 // 20\n"));
+    assert!(!listing.contains("if_jmp"));
+
+    for program in [&program[..], &optimized[..]].iter() {
+        let mut process = Process::new(program);
+        assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+        assert_eq!(process.pop_u8(), Ok(5));
+        assert!(process.pop_u8().is_err());
+    }
+
+    assert!(vm::verify(&optimized).is_ok());
+    let analysis = vm::analyze(&optimized);
+    assert!(analysis.is_ok());
+    assert_eq!(analysis.get_max_depth(), Some(3));
+
+    // the builder emits the same fused branches
+    let mut builder = ProgramBuilder::new();
+    let top = builder.label();
+    let done = builder.label();
+    builder.push_u8(0);
+    builder.bind(top).push_u8(1).add_u8().copy_u8().push_u8(5).br_lt_u8(top);
+    builder.copy_u8().br_nonzero_u8(done).pop_u8().push_u8(99);
+    builder.bind(done).nop();
+    assert_eq!(&builder.build().unwrap()[..], &optimized[..]);
+}
+
+#[test]
+fn test_optimize_targets() {
+    // if_jmp is jumped to on its own, the pair is kept
+    let program = vm::assemble("
+        push_u8 1
+        push_u8 2
+        eq_u8
+        push_u8 0
+        jmp check
+        nop
+    check:
+        if_jmp end
+    end:
+        nop
+    ").unwrap();
+    assert_eq!(&vm::optimize(&program).unwrap()[..], &program[..]);
+
+    // the header and its encoding are kept
+    let program = vm::assemble(&format!(".encoding varint\n{}", SOURCE)).unwrap();
+    let optimized = vm::optimize(&program).unwrap();
+    assert!(disassemble(&optimized).starts_with(".encoding varint\n"));
+    let mut process = Process::new(&optimized);
+    assert_eq!(process.get_encoding(), Encoding::Varint);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(5));
+
+    let error = vm::optimize(&vm::assemble("push_u64 3\njmp_indirect").unwrap()).err().unwrap();
+    assert_eq!(error.get_kind(), OptimizeErrorKind::UnknownTarget);
+    assert_eq!(error.get_offset(), 4);
+
+    let error = vm::optimize(&vm::assemble("jmp 1\nnop").unwrap()).err().unwrap();
+    assert_eq!(error.get_kind(), OptimizeErrorKind::InvalidTarget(1));
+}
+
+#[test]
+fn test_optimize_neq() {
+    // neq of a non-zero value branches over a jmp back to the loop
+    let program = vm::assemble("
+        push_u8 0
+    loop:
+        push_u8 1
+        add_u8
+        copy_u8
+        push_u8 5
+        neq_u8
+        if_jmp loop
+        nop
+    ").unwrap();
+    let optimized = vm::optimize(&program).unwrap();
+
+    let listing = disassemble(&optimized);
+    assert!(listing.contains("    br_eq_u8 label_"));
+    assert!(listing.contains("    jmp label_4 // "));
+    assert!(!listing.contains("neq_u8"));
+    assert!(!listing.contains("if_jmp"));
+    assert!(vm::verify(&optimized).is_ok());
+    assert!(vm::analyze(&optimized).is_ok());
+
+    for program in [&program[..], &optimized[..]].iter() {
+        let mut process = Process::new(program);
+        assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+        assert_eq!(process.pop_u8(), Ok(5));
+    }
+
+    let mut builder = ProgramBuilder::new();
+    let top = builder.label();
+    let skip = builder.label();
+    builder.push_u8(0);
+    builder.bind(top).push_u8(1).add_u8().copy_u8().push_u8(5).br_eq_u8(skip).jmp(top);
+    builder.bind(skip).nop();
+    assert_eq!(&builder.build().unwrap()[..], &optimized[..]);
+}
@@ -0,0 +1,67 @@
+extern crate vm;
+
+
+use vm::{Process, Status, State, ProgramBuilder, TrapKind, ValueType, StackErrorKind};
+
+
+#[test]
+fn test_halt() {
+    // nothing after halt runs
+    let program = vm::assemble("
+        push_u8 1
+        halt
+        push_u8 2
+    ").unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(process.get_exit_code(), None);
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
+    assert_eq!(process.get_state(), State::Terminated);
+    assert_eq!(process.get_exit_code(), Some(0));
+    assert_eq!(process.pop_u8(), Ok(1));
+    assert!(process.pop_u8().is_err());
+
+    // halt is final, running again evaluates nothing
+    process.push_u8(1);
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
+    assert_eq!(process.get_state(), State::Terminated);
+    assert_eq!(process.pop_u8(), Ok(1));
+    assert!(process.pop_u8().is_err());
+}
+
+#[test]
+fn test_halt_with() {
+    let program = vm::assemble("
+        push_u8 7
+        push_u32 42
+        halt_with
+        pop_u8
+    ").unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(42)));
+    assert_eq!(process.get_exit_code(), Some(42));
+    assert_eq!(process.pop_u8(), Ok(7));
+
+    let program = vm::assemble("push_u16 1\nhalt_with").unwrap();
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::StackUnderflow);
+    assert_eq!(process.get_exit_code(), None);
+    assert_eq!(process.get_trap(), Some(trap));
+    assert_eq!(vm::run(&mut process), Err(trap));
+
+    let mut builder = ProgramBuilder::new();
+    builder.push_u8(7).push_u32(42).halt_with().pop_u8();
+    assert_eq!(&builder.build().unwrap()[..], &vm::assemble("push_u8 7\npush_u32 42\nhalt_with\npop_u8").unwrap()[..]);
+}
+
+#[test]
+fn test_halt_analysis() {
+    // the path stops at halt_with, pop_u8 after it is never reached
+    let analysis = vm::analyze(&vm::assemble("push_u32 3\nhalt_with\npop_u8").unwrap());
+    assert!(analysis.is_ok());
+    assert_eq!(analysis.get_shape(6), None);
+
+    let errors = vm::analyze(&vm::assemble("push_u8 3\nhalt_with").unwrap()).get_errors().to_vec();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].get_kind(), StackErrorKind::WidthMismatch(ValueType::U32, ValueType::U8));
+}
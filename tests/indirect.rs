@@ -0,0 +1,60 @@
+extern crate vm;
+
+
+use vm::{Process, Status, Trap, TrapKind, StackErrorKind};
+
+
+const SOURCE: &'static str = "
+    push_u8 3
+    push_u64 double
+    call_indirect
+    push_u64 end
+    jmp_indirect
+double:
+    push_u8 2
+    mul_u8
+    ret
+end:
+    nop
+";
+
+
+#[test]
+fn test_indirect_branches() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(6));
+    assert_eq!(process.pop_u8().map_err(|trap| trap.get_kind()), Err(TrapKind::StackUnderflow));
+}
+
+#[test]
+fn test_indirect_entry_points() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    let mut process = Process::new(&program);
+    process.set_entry_points(&[22, 16]);
+    assert_eq!(process.get_entry_points(), Some(&[16, 22][..]));
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(6));
+
+    // end is not an entry point
+    let mut process = Process::new(&program);
+    process.set_entry_points(&[16]);
+    assert_eq!(vm::run(&mut process), Err(Trap::new(TrapKind::InvalidTarget(22), 14)));
+
+    process.clear_entry_points();
+    assert_eq!(process.get_entry_points(), None);
+}
+
+#[test]
+fn test_indirect_analysis() {
+    let program = vm::assemble(SOURCE).unwrap();
+    let analysis = vm::analyze(&program);
+
+    assert_eq!(analysis.get_errors().len(), 1);
+    assert_eq!(analysis.get_errors()[0].get_kind(), StackErrorKind::UnknownTarget);
+    assert_eq!(analysis.get_errors()[0].get_offset(), 8);
+    assert!(vm::verify(&program).is_ok());
+}
@@ -0,0 +1,58 @@
+extern crate vm;
+
+
+use vm::{Process, Status, Instr, TrapKind};
+
+
+#[test]
+fn test_memory() {
+    let program = [
+        // save 0x1234 at 2
+        Instr::push_u16 as u8, Instr::type_int as u8, Instr::size_16 as u8, 0x12, 0x34,
+        Instr::push_u64 as u8, Instr::type_int as u8, Instr::size_8 as u8, 2,
+        Instr::save_u16 as u8,
+        // load it back through a pointer operand and an address on the stack
+        Instr::push_u16 as u8, Instr::type_ptr as u8, Instr::size_8 as u8, 2,
+        Instr::push_u16 as u8, Instr::type_ptr_off as u8, Instr::size_8 as u8, 1, Instr::size_8 as u8, 1,
+        Instr::push_u64 as u8, Instr::type_int as u8, Instr::size_8 as u8, 2,
+        Instr::load_u16 as u8,
+    ];
+    let mut process = Process::with_memory(&program, 4);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.get_memory(), &[0, 0, 0x12, 0x34]);
+    assert_eq!(process.pop_u16(), Ok(0x1234));
+    assert_eq!(process.pop_u16(), Ok(0x1234));
+    assert_eq!(process.pop_u16(), Ok(0x1234));
+
+    let mut process = Process::with_memory(&program, 3);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::MemoryOutOfBounds(2));
+    assert_eq!(trap.get_program_counter(), 9);
+
+    assert_eq!(process.grow_memory(1), 3);
+    assert_eq!(process.get_memory_size(), 4);
+}
+
+#[test]
+fn test_raw_memory() {
+    // host memory is big-endian like process memory
+    let value: u32 = 0xdeadbeefu32.to_be();
+    let address = &value as *const u32 as u64;
+
+    let program = [Instr::halt as u8];
+    let mut process = Process::new(&program);
+    assert!(process.read_mem_u32(address as usize).is_err());
+    unsafe {
+        process.set_raw_memory(true);
+    }
+    assert_eq!(process.read_mem_u32(address as usize), Ok(0xdeadbeef));
+
+    // addresses need not be aligned
+    let mut bytes = [0u8, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+    let address = bytes.as_mut_ptr() as usize + 1;
+    assert_eq!(process.read_mem_u64(address), Ok(0x0102030405060708));
+    assert_eq!(process.read_mem_u16(address + 2), Ok(0x0304));
+    assert_eq!(process.write_mem_u32(address, 0xa1b2c3d4), Ok(()));
+    assert_eq!(process.write_mem_u16(address + 6, 0xe5f6), Ok(()));
+    assert_eq!(bytes, [0, 0xa1, 0xb2, 0xc3, 0xd4, 5, 6, 0xe5, 0xf6, 9]);
+}
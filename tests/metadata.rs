@@ -0,0 +1,56 @@
+extern crate vm;
+
+
+use std::convert::TryFrom;
+
+use vm::{Process, Instr, OperandKind, ValueType, StackEffect};
+
+
+#[test]
+fn test_instr_metadata() {
+    assert_eq!(Instr::push_i16.operands(), &[OperandKind::Immediate][..]);
+    assert_eq!(Instr::if_jmp.operands(), &[OperandKind::Target][..]);
+    assert_eq!(Instr::add_u8.operands(), &[][..]);
+    assert_eq!(Instr::push_i16.value_type(), Some(ValueType::I16));
+    assert_eq!(Instr::cvt_u64_f32.value_type(), Some(ValueType::F32));
+    assert_eq!(Instr::ret.value_type(), None);
+    assert_eq!(Instr::save_u16.pops(), &[ValueType::U64, ValueType::U16][..]);
+    assert_eq!(Instr::lt_f64.stack_effect(), StackEffect {popped: 16, pushed: 1});
+    assert!(Instr::neg_i8.is_prefixable() && !Instr::neg_f32.is_prefixable());
+
+    // stack effects match what evaluating the instruction does
+    let instrs = (0..Instr::count()).map(|opcode| Instr::try_from(opcode as u8).unwrap())
+        .chain((0..Instr::extended_count()).map(|opcode| Instr::from_extended(opcode as u8).unwrap()));
+
+    for instr in instrs {
+        match instr {
+            Instr::halt | Instr::if_jmp | Instr::jmp | Instr::call | Instr::ret |
+            Instr::checked | Instr::sat | Instr::ext => continue,
+            _ if !instr.operands().is_empty() || instr.mnemonic().starts_with("type_") ||
+                instr.mnemonic().starts_with("size_") => continue,
+            _ => (),
+        }
+
+        let program = match instr.extended_opcode() {
+            Some(opcode) => vec![Instr::ext as u8, opcode],
+            None => vec![instr as u8],
+        };
+        let mut process = Process::with_memory(&program, 16);
+        process.push_u64(0x0101_0101_0101_0101);
+        process.push_u64(0x0101_0101_0101_0101);
+        // address for load and save, a non-zero value for everything else
+        process.push_u64(if instr.mnemonic().starts_with("load_") || instr.mnemonic().starts_with("save_") {
+            0
+        } else {
+            0x0101_0101_0101_0101
+        });
+        assert!(vm::run(&mut process).is_ok(), "{:?}", instr);
+
+        let mut depth = 0;
+        while process.pop_u8().is_ok() {
+            depth += 1;
+        }
+        let effect = instr.stack_effect();
+        assert_eq!(depth, 24 - effect.popped + effect.pushed, "{:?}", instr);
+    }
+}
@@ -0,0 +1,66 @@
+extern crate vm;
+
+
+use vm::{Process, Status, Instr, TrapKind};
+
+
+#[test]
+fn test_overflow() {
+    let push_255 = [Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 255];
+    let push_1 = [Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 1];
+
+    let program = [&push_255[..], &push_1[..], &[Instr::add_u8 as u8]].concat();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(0));
+
+    let program = [&push_255[..], &push_1[..], &[Instr::checked as u8, Instr::add_u8 as u8]].concat();
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::Overflow);
+    assert_eq!(trap.get_program_counter(), 8);
+
+    let program = [&push_255[..], &push_1[..], &[Instr::sat as u8, Instr::add_u8 as u8]].concat();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(255));
+
+    let program = [&push_1[..], &push_255[..], &[Instr::sat as u8, Instr::sub_u8 as u8]].concat();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(0));
+
+    // i8::MIN
+    let push_min = [Instr::push_i8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 0x80];
+
+    let program = [&push_min[..], &[Instr::neg_i8 as u8]].concat();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(0x80));
+
+    let program = [&push_min[..], &[Instr::sat as u8, Instr::neg_i8 as u8]].concat();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(0x7f));
+
+    let program = [&push_min[..], &push_255[..], &[Instr::checked as u8, Instr::mul_i8 as u8]].concat();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).unwrap_err().get_kind(), TrapKind::Overflow);
+
+    let program = [&push_min[..], &push_255[..], &[Instr::mul_i8 as u8]].concat();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(0x80));
+
+    // shift amounts are masked to the width of the type
+    let push_9 = [Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 9];
+    let program = [&push_1[..], &push_9[..], &[Instr::shl_u8 as u8]].concat();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(2));
+
+    // only integer add, sub, mul and neg take a prefix
+    let program = [&push_1[..], &push_1[..], &[Instr::checked as u8, Instr::div_u8 as u8]].concat();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).unwrap_err().get_kind(), TrapKind::InvalidInstr(Instr::div_u8 as u8));
+}
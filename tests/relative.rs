@@ -0,0 +1,104 @@
+extern crate vm;
+
+
+use vm::{Process, Status, Instr, ProgramBuilder, VerifyErrorKind};
+
+
+const SOURCE: &'static str = "
+    push_u8 0
+loop:
+    push_u8 1
+    add_u8
+    copy_u8
+    push_u8 5
+    neq_u8
+    if_jmp_rel loop
+    call_rel double
+    jmp_rel end
+double:
+    push_u8 2
+    mul_u8
+    ret
+end:
+    nop
+";
+
+
+fn disassemble(program: &[u8]) -> String {
+    let mut listing = String::new();
+    vm::disassemble(program, &mut listing).unwrap();
+    listing
+}
+
+
+#[test]
+fn test_relative_encoding() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    assert_eq!(&program[15..28], &[
+        Instr::ext as u8, Instr::if_jmp_rel.extended_opcode().unwrap(),
+        Instr::type_int as u8, Instr::size_32 as u8, 0xff, 0xff, 0xff, 0xf5,
+        Instr::ext as u8, Instr::call_rel.extended_opcode().unwrap(),
+        Instr::type_int as u8, Instr::size_8 as u8, 10,
+    ][..]);
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(10));
+}
+
+#[test]
+fn test_relative_position_independent() {
+    let fragment = vm::assemble(SOURCE).unwrap();
+
+    // the same bytes run anywhere in the program
+    let mut program = vec![Instr::nop as u8; 3];
+    program.extend_from_slice(&fragment);
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(10));
+
+    assert!(vm::verify(&program).is_ok());
+    let analysis = vm::analyze(&program);
+    assert!(analysis.is_ok());
+    assert_eq!(analysis.get_max_depth(), Some(3));
+}
+
+#[test]
+fn test_relative_tooling() {
+    let program = vm::assemble("
+        push_u8 1
+        back: if_jmp_rel forward
+        jmp_rel back
+        forward: halt
+    ").unwrap();
+
+    assert_eq!(disassemble(&program), "    \
+        push_u8 1 // 0\n\
+        label_4:\n    \
+        if_jmp_rel label_17 // 4\n    \
+        jmp_rel label_4 // 9\n\
+        label_17:\n    \
+        halt // 17\n\
+    ");
+    assert_eq!(&vm::assemble(&disassemble(&program)).unwrap()[..], &program[..]);
+
+    let decoded = vm::decode(&program, 9).unwrap();
+    assert_eq!(decoded.get_target(), Some(4));
+
+    // the builder lays relative labels out like the assembler
+    let mut builder = ProgramBuilder::new();
+    let back = builder.label();
+    let forward = builder.label();
+    builder.push_u8(1);
+    builder.bind(back).if_jmp_rel(forward).jmp_rel(back);
+    builder.bind(forward).halt();
+    assert_eq!(&builder.build().unwrap()[..], &program[..]);
+
+    // lands inside push_u8
+    let errors = vm::verify(&vm::assemble("push_u8 1\njmp_rel -3").unwrap()).err().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].get_kind(), VerifyErrorKind::InvalidTarget(1));
+    assert_eq!(errors[0].get_offset(), 4);
+}
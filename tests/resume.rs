@@ -0,0 +1,129 @@
+extern crate vm;
+
+
+use vm::{Process, Status, State, ProgramBuilder, TrapKind};
+
+
+const SOURCE: &'static str = "
+    push_u8 0
+loop:
+    push_u8 1
+    add_u8
+    copy_u8
+    push_u8 5
+    neq_u8
+    if_jmp loop
+end:
+    nop
+";
+
+
+#[test]
+fn test_run_for() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run_for(&mut process, 0), Ok(Status::OutOfBudget));
+    assert_eq!(process.get_program_counter(), 0);
+
+    // one push, five passes of six instructions and the nop
+    let mut slices = 0;
+    while vm::run_for(&mut process, 4) == Ok(Status::OutOfBudget) {
+        assert_eq!(process.get_state(), State::Running);
+        slices += 1;
+    }
+    assert_eq!(slices, 8);
+    assert_eq!(process.get_state(), State::Terminated);
+    assert_eq!(process.pop_u8(), Ok(5));
+
+    let mut process = Process::new(&program);
+    let mut steps = 0;
+    loop {
+        steps += 1;
+        match vm::step(&mut process) {
+            Ok(Status::OutOfBudget) => (),
+            status => {
+                assert_eq!(status, Ok(Status::Finished));
+                break;
+            },
+        }
+    }
+    // the last step runs off the end
+    assert_eq!(steps, 33);
+    assert_eq!(process.pop_u8(), Ok(5));
+}
+
+#[test]
+fn test_pause() {
+    let program = vm::assemble("
+        push_u8 1
+        pause
+        push_u8 2
+        pause
+    ").unwrap();
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Yielded));
+    assert_eq!(process.get_state(), State::Running);
+    assert_eq!(vm::run_for(&mut process, 10), Ok(Status::Yielded));
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(2));
+    assert_eq!(process.pop_u8(), Ok(1));
+}
+
+#[test]
+fn test_wait() {
+    let mut builder = ProgramBuilder::new();
+    builder.push_u8(1).push_u64(9).wait().push_u8(2);
+    let program = builder.build().unwrap();
+    assert_eq!(&program[..], &vm::assemble("push_u8 1\npush_u64 9\nwait\npush_u8 2").unwrap()[..]);
+
+    let mut process = Process::new(&program);
+    assert_eq!(process.get_event(), None);
+    assert_eq!(vm::run_for(&mut process, 10), Ok(Status::Waiting));
+    assert_eq!(process.get_state(), State::Waiting);
+    assert_eq!(process.get_event(), Some(9));
+
+    // nothing runs until woken
+    let counter = process.get_program_counter();
+    assert_eq!(vm::run(&mut process), Ok(Status::Waiting));
+    assert_eq!(vm::step(&mut process), Ok(Status::Waiting));
+    assert_eq!(process.get_program_counter(), counter);
+
+    process.wake();
+    assert_eq!(process.get_event(), None);
+    assert_eq!(process.get_state(), State::Running);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(2));
+    assert_eq!(process.pop_u8(), Ok(1));
+}
+
+#[test]
+fn test_terminated() {
+    // a stopped process is not evaluated again, it keeps returning how it stopped
+    let program = vm::assemble("push_u8 1\nhalt\npush_u8 2").unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
+    let counter = process.get_program_counter();
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
+    assert_eq!(vm::run_for(&mut process, 10), Ok(Status::Halted(0)));
+    assert_eq!(vm::step(&mut process), Ok(Status::Halted(0)));
+    assert_eq!(process.get_state(), State::Terminated);
+    assert_eq!(process.get_program_counter(), counter);
+    assert_eq!(process.pop_u8(), Ok(1));
+    assert!(process.pop_u8().is_err());
+
+    let program = vm::assemble("push_u8 1").unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(vm::step(&mut process), Ok(Status::Finished));
+    assert_eq!(process.get_trap(), None);
+
+    let program = vm::assemble("pop_u8\npush_u8 2").unwrap();
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::StackUnderflow);
+    assert_eq!(process.get_trap(), Some(trap));
+    assert_eq!(vm::run(&mut process), Err(trap));
+    assert!(process.pop_u8().is_err());
+}
@@ -0,0 +1,126 @@
+extern crate vm;
+
+
+use vm::{Process, Scheduler, Pid, Status, State, CostTable, Trap, TrapKind};
+
+
+const COUNTER: &'static str = "
+    push_u8 0
+loop:
+    push_u8 1
+    add_u8
+    copy_u8
+    push_u8 3
+    neq_u8
+    if_jmp loop
+";
+
+
+#[test]
+fn test_round_robin() {
+    let counter = vm::assemble(COUNTER).unwrap();
+    let halt = vm::assemble("push_u32 7\nhalt_with").unwrap();
+
+    let mut scheduler = Scheduler::new(4);
+    let a = scheduler.add(Process::new(&counter));
+    let b = scheduler.add(Process::new(&halt));
+    let c = scheduler.add(Process::new(&counter));
+    assert_eq!((a, b, c), (Pid(0), Pid(1), Pid(2)));
+    assert_eq!(scheduler.len(), 3);
+    assert_eq!(scheduler.pids().collect::<Vec<_>>(), vec![a, b, c]);
+
+    assert_eq!(scheduler.run_slice(), Some((a, Ok(Status::OutOfBudget))));
+    assert_eq!(scheduler.run_slice(), Some((b, Ok(Status::Halted(7)))));
+    assert_eq!(scheduler.run_slice(), Some((c, Ok(Status::OutOfBudget))));
+
+    // b was reaped, a and c alternate until they finish
+    assert!(scheduler.get(b).is_none());
+    assert_eq!(scheduler.pids().collect::<Vec<_>>(), vec![a, c]);
+
+    let mut order = Vec::new();
+    while let Some((pid, status)) = scheduler.run_slice() {
+        order.push(pid);
+        if status == Ok(Status::Finished) {
+            assert!(scheduler.get(pid).is_none());
+        } else {
+            assert_eq!(status, Ok(Status::OutOfBudget));
+            assert_eq!(scheduler.get(pid).unwrap().get_state(), State::Running);
+        }
+    }
+    assert_eq!(order, vec![a, c, a, c, a, c, a, c]);
+    assert!(scheduler.is_empty());
+}
+
+#[test]
+fn test_waiting() {
+    let program = vm::assemble("
+        push_u8 1
+        push_u64 5
+        wait
+        push_u8 2
+    ").unwrap();
+    let other = vm::assemble("push_u64 6\nwait").unwrap();
+
+    let mut scheduler = Scheduler::new(100);
+    let a = scheduler.add(Process::new(&program));
+    let b = scheduler.add(Process::new(&other));
+    let c = scheduler.add(Process::new(&program));
+
+    assert_eq!(scheduler.run_slice(), Some((a, Ok(Status::Waiting))));
+    assert_eq!(scheduler.run_slice(), Some((b, Ok(Status::Waiting))));
+    assert_eq!(scheduler.run_slice(), Some((c, Ok(Status::Waiting))));
+    assert_eq!(scheduler.run_slice(), None);
+    assert_eq!(scheduler.len(), 3);
+
+    assert_eq!(scheduler.notify(4), 0);
+    assert_eq!(scheduler.notify(5), 2);
+    assert_eq!(scheduler.run_slice(), Some((a, Ok(Status::Finished))));
+    assert_eq!(scheduler.run_slice(), Some((c, Ok(Status::Finished))));
+    assert_eq!(scheduler.run_slice(), None);
+
+    assert!(!scheduler.wake(a));
+    assert!(scheduler.wake(b));
+    assert!(!scheduler.wake(b));
+    assert_eq!(scheduler.run_slice(), Some((b, Ok(Status::Finished))));
+    assert!(scheduler.is_empty());
+}
+
+#[test]
+fn test_traps_and_fuel() {
+    let program = vm::assemble("push_u8 1\npush_u8 0\ndiv_u8").unwrap();
+    let counter = vm::assemble(COUNTER).unwrap();
+
+    let mut scheduler = Scheduler::new(100);
+    let a = scheduler.add(Process::new(&program));
+    let mut metered = Process::new(&counter);
+    metered.set_cost_table(CostTable::new());
+    metered.set_fuel(6);
+    let b = scheduler.add(metered);
+
+    assert_eq!(scheduler.run_slice(), Some((a, Err(Trap::new(TrapKind::DivideByZero, 8)))));
+    assert!(scheduler.get(a).is_none());
+
+    // out of fuel parks the process, it stays Running and only refuel puts
+    // it back into the rotation
+    assert_eq!(scheduler.run_slice(), Some((b, Err(Trap::new(TrapKind::OutOfFuel, 10)))));
+    assert_eq!(scheduler.get(b).unwrap().get_state(), State::Running);
+    assert!(scheduler.is_parked(b));
+    assert_eq!(scheduler.run_slice(), None);
+    assert_eq!(scheduler.notify(0), 0);
+    assert!(!scheduler.wake(b));
+    assert_eq!(scheduler.run_slice(), None);
+
+    let d = scheduler.add(Process::new(&counter));
+    assert_eq!(scheduler.run_slice(), Some((d, Ok(Status::Finished))));
+    assert_eq!(scheduler.refuel(b, 100), Some(100));
+    assert!(!scheduler.is_parked(b));
+    assert_eq!(scheduler.run_slice(), Some((b, Ok(Status::Finished))));
+    assert_eq!(scheduler.refuel(b, 1), None);
+
+    let c = scheduler.add(Process::new(&counter));
+    assert_eq!(c, Pid(3));
+    let process = scheduler.remove(c).unwrap();
+    assert_eq!(process.get_program_counter(), 0);
+    assert!(scheduler.remove(c).is_none());
+    assert_eq!(scheduler.run_slice(), None);
+}
@@ -0,0 +1,97 @@
+extern crate vm;
+
+
+use vm::{Process, Scheduler, Pid, Status, State, CostTable, ProgramBuilder, Trap, TrapKind};
+
+
+const SOURCE: &'static str = "
+    push_u8 1
+    push_u32 7
+    spawn worker, 4
+    wrap_u32
+    halt_with
+worker:
+    halt_with
+";
+
+
+#[test]
+fn test_spawn() {
+    let program = vm::assemble(SOURCE).unwrap();
+    assert!(vm::analyze(&program).is_ok());
+    let mut listing = String::new();
+    vm::disassemble(&program, &mut listing).unwrap();
+    assert!(listing.contains("    spawn label_"));
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Spawned));
+    assert!(process.is_spawning());
+    assert_eq!(process.get_state(), State::Running);
+
+    // the parent does not run on without the child's pid
+    let counter = process.get_program_counter();
+    assert_eq!(vm::run(&mut process), Ok(Status::Spawned));
+    assert_eq!(vm::run_for(&mut process, 10), Ok(Status::Spawned));
+    assert_eq!(vm::step(&mut process), Ok(Status::Spawned));
+    assert_eq!(process.get_program_counter(), counter);
+    assert_eq!(process.pop_u8(), Ok(1));
+    assert!(process.pop_u8().is_err());
+
+    let mut child = process.take_spawned().unwrap();
+    assert!(!process.is_spawning());
+    assert!(process.take_spawned().is_none());
+    assert_eq!(child.get_state(), State::New);
+    assert_eq!(child.peek_u32(0), Ok(7));
+
+    process.push_u8(1);
+    process.push_u64(5);
+    assert_eq!(vm::run(&mut child), Ok(Status::Halted(7)));
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(5)));
+    assert_eq!(process.pop_u8(), Ok(1));
+
+    let program = vm::assemble("push_u8 1\nspawn 0, 2").unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Err(Trap::new(TrapKind::StackUnderflow, 4)));
+}
+
+#[test]
+fn test_spawn_builder() {
+    let mut builder = ProgramBuilder::new();
+    let worker = builder.label();
+    builder.push_u8(1).push_u32(7).spawn(worker, 4).wrap_u32().halt_with()
+        .bind(worker).halt_with();
+    assert_eq!(&builder.build().unwrap()[..], &vm::assemble(SOURCE).unwrap()[..]);
+}
+
+#[test]
+fn test_spawn_scheduled() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    let mut scheduler = Scheduler::new(100);
+    let parent = scheduler.add(Process::new(&program));
+    assert_eq!(scheduler.run_slice(), Some((parent, Ok(Status::Spawned))));
+    assert_eq!(scheduler.pids().collect::<Vec<_>>(), vec![parent, Pid(1)]);
+    assert_eq!(scheduler.get(parent).unwrap().peek_u64(0), Ok(1));
+
+    assert_eq!(scheduler.run_slice(), Some((Pid(1), Ok(Status::Halted(7)))));
+    assert_eq!(scheduler.run_slice(), Some((parent, Ok(Status::Halted(1)))));
+    assert_eq!(scheduler.run_slice(), None);
+
+    // a metered child waits for fuel of its own
+    let mut process = Process::new(&program);
+    process.set_cost_table(CostTable::new());
+    process.set_fuel(100);
+    let parent = scheduler.add(process);
+    assert_eq!(scheduler.run_slice(), Some((parent, Ok(Status::Spawned))));
+
+    let child = Pid(3);
+    assert_eq!(scheduler.run_slice().map(|(pid, status)| (pid, status.map_err(|trap| trap.get_kind()))),
+        Some((child, Err(TrapKind::OutOfFuel))));
+    assert!(scheduler.is_parked(child));
+    assert_eq!(scheduler.run_slice(), Some((parent, Ok(Status::Halted(3)))));
+    assert_eq!(scheduler.run_slice(), None);
+
+    assert_eq!(scheduler.refuel(child, 10), Some(10));
+    assert_eq!(scheduler.run_slice(), Some((child, Ok(Status::Halted(7)))));
+    assert!(scheduler.is_empty());
+}
@@ -0,0 +1,96 @@
+extern crate vm;
+
+
+use vm::{Process, Status, ProgramBuilder, TrapKind, ValueType, StackErrorKind};
+
+
+const SOURCE: &'static str = "
+    push_u8 1
+    push_u16 2
+    push_u16 3
+    push_u16 4
+    rot_u16
+    swap_u16
+    over_u16
+    pick_u16 3
+    roll_u16 4
+    drop_bytes 2
+";
+
+
+#[test]
+fn test_stack_shuffle() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    // 2 3 4 -> 3 4 2 -> 3 2 4 -> 3 2 4 2 -> 3 2 4 2 3 -> 2 4 2 3 3 -> 2 4 2 3
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u16(), Ok(3));
+    assert_eq!(process.pop_u16(), Ok(2));
+    assert_eq!(process.pop_u16(), Ok(4));
+    assert_eq!(process.pop_u16(), Ok(2));
+    assert_eq!(process.pop_u8(), Ok(1));
+    assert_eq!(process.pop_u8().map_err(|trap| trap.get_kind()), Err(TrapKind::StackUnderflow));
+}
+
+#[test]
+fn test_stack_widths() {
+    let program = vm::assemble("
+        push_f64 1.5
+        push_f64 -2.0
+        swap_f64
+        pick_f64 1
+        push_u8 7
+        push_u8 8
+        push_u8 9
+        roll_u8 2
+    ").unwrap();
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Finished));
+    assert_eq!(process.pop_u8(), Ok(7));
+    assert_eq!(process.pop_u8(), Ok(9));
+    assert_eq!(process.pop_u8(), Ok(8));
+    assert_eq!(process.pop_f64(), Ok(-2.0));
+    assert_eq!(process.pop_f64(), Ok(1.5));
+    assert_eq!(process.pop_f64(), Ok(-2.0));
+}
+
+#[test]
+fn test_stack_traps() {
+    for source in ["push_u8 1\nswap_u8", "push_u32 1\nrot_u16", "push_u16 1\npick_u16 1", "push_u8 1\ndrop_bytes 2"].iter() {
+        let program = vm::assemble(source).unwrap();
+        let mut process = Process::new(&program);
+        assert_eq!(vm::run(&mut process).map_err(|trap| trap.get_kind()), Err(TrapKind::StackUnderflow), "{}", source);
+    }
+
+    // the depth overflows usize
+    let program = vm::assemble("push_u64 1\nroll_u64 0xffffffffffffffff").unwrap();
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).map_err(|trap| trap.get_kind()), Err(TrapKind::StackUnderflow));
+}
+
+#[test]
+fn test_stack_tooling() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    let mut listing = String::new();
+    vm::disassemble(&program, &mut listing).unwrap();
+    assert!(listing.contains("    pick_u16 3 // 22\n"));
+    assert_eq!(&vm::assemble(&listing).unwrap()[..], &program[..]);
+
+    assert!(vm::verify(&program).is_ok());
+    let analysis = vm::analyze(&program);
+    assert!(analysis.is_ok());
+    assert_eq!(analysis.get_shape(22), Some(&[ValueType::U8, ValueType::U16, ValueType::U16, ValueType::U16, ValueType::U16][..]));
+    assert_eq!(analysis.get_max_depth(), Some(11));
+
+    let errors = vm::analyze(&vm::assemble("push_u8 1\npush_u16 2\npick_u16 1").unwrap()).get_errors().to_vec();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].get_kind(), StackErrorKind::WidthMismatch(ValueType::U16, ValueType::U8));
+
+    let mut builder = ProgramBuilder::new();
+    builder.push_u8(1).push_u16(2).push_u16(3).push_u16(4);
+    builder.rot_u16().swap_u16().over_u16().pick_u16(3).roll_u16(4).drop_bytes(2);
+    assert_eq!(&builder.build().unwrap()[..], &program[..]);
+}
@@ -1,7 +1,7 @@
 extern crate vm;
 
 
-use vm::{Process, Instr, State};
+use vm::{Process, Status, Instr, State};
 
 
 /*
@@ -50,10 +50,10 @@ static PROGRAM: [u8; 30] = [
 #[test]
 fn test_next() {
     let mut process = Process::new(&PROGRAM);
-    assert_eq!(process.next_u8(), Instr::call as u8);
-    assert_eq!(process.next_u8(), Instr::type_int as u8);
-    assert_eq!(process.next_u8(), Instr::size_8 as u8);
-    assert_eq!(process.next_u8(), 8);
+    assert_eq!(process.next_u8(), Ok(Instr::call as u8));
+    assert_eq!(process.next_u8(), Ok(Instr::type_int as u8));
+    assert_eq!(process.next_u8(), Ok(Instr::size_8 as u8));
+    assert_eq!(process.next_u8(), Ok(8));
 }
 
 #[test]
@@ -63,15 +63,15 @@ fn test_push_pop() {
     process.push_u16(2);
     process.push_u32(3);
     process.push_u64(4);
-    assert_eq!(process.pop_u64(), 4);
-    assert_eq!(process.pop_u32(), 3);
-    assert_eq!(process.pop_u16(), 2);
-    assert_eq!(process.pop_u8(), 1);
+    assert_eq!(process.pop_u64(), Ok(4));
+    assert_eq!(process.pop_u32(), Ok(3));
+    assert_eq!(process.pop_u16(), Ok(2));
+    assert_eq!(process.pop_u8(), Ok(1));
 }
 
 #[test]
 fn test_full_program() {
     let mut process = Process::new(&PROGRAM);
-    vm::run(&mut process);
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
     assert_eq!(process.get_state(), State::Terminated);
 }
@@ -0,0 +1,48 @@
+extern crate vm;
+
+
+use vm::{Process, Instr, State, TrapKind};
+
+
+#[test]
+fn test_traps() {
+    let program = [Instr::nop as u8, Instr::add_u8 as u8];
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::StackUnderflow);
+    assert_eq!(trap.get_program_counter(), 1);
+    assert_eq!(process.get_state(), State::Terminated);
+
+    let program = [Instr::push_u16 as u8, Instr::type_int as u8, Instr::size_16 as u8, 0];
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).unwrap_err().get_kind(), TrapKind::TruncatedProgram);
+
+    let program = [Instr::push_u8 as u8, Instr::type_int as u8, Instr::nop as u8, 0];
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).unwrap_err().get_kind(), TrapKind::InvalidSize(Instr::nop as u8));
+
+    let program = [Instr::nop as u8, Instr::type_int as u8];
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::InvalidInstr(Instr::type_int as u8));
+    assert_eq!(trap.get_program_counter(), 1);
+
+    let program = [
+        Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 1,
+        Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 0,
+        Instr::div_u8 as u8,
+    ];
+    let mut process = Process::new(&program);
+    let trap = vm::run(&mut process).unwrap_err();
+    assert_eq!(trap.get_kind(), TrapKind::DivideByZero);
+    assert_eq!(trap.get_program_counter(), 8);
+
+    // popping an empty stack
+    let program = [Instr::nop as u8];
+    let mut process = Process::new(&program);
+    assert_eq!(process.pop_u8().unwrap_err().get_kind(), TrapKind::StackUnderflow);
+
+    let program = [Instr::ret as u8];
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).unwrap_err().get_kind(), TrapKind::CallStackUnderflow);
+}
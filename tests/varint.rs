@@ -0,0 +1,140 @@
+extern crate vm;
+
+
+use vm::{Process, Status, Instr, Encoding, ProgramBuilder, TrapKind, DecodeErrorKind};
+
+
+const SOURCE: &'static str = "
+.encoding varint
+    push_i8 -2
+    push_u64 300
+    push_i64 -1000000
+    jmp end
+    push_u8 9
+end:
+    halt
+";
+
+
+fn disassemble(program: &[u8]) -> String {
+    let mut listing = String::new();
+    vm::disassemble(program, &mut listing).unwrap();
+    listing
+}
+
+
+#[test]
+fn test_varint_encoding() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    assert_eq!(&program[..], &[
+        0xff, b'v', b'm', 1,
+        Instr::push_i8 as u8, Instr::type_int as u8, 3,
+        Instr::push_u64 as u8, Instr::type_int as u8, 0xac, 0x02,
+        Instr::push_i64 as u8, Instr::type_int as u8, 0xff, 0x88, 0x7a,
+        Instr::jmp as u8, Instr::type_int as u8, 22,
+        Instr::push_u8 as u8, Instr::type_int as u8, 9,
+        Instr::halt as u8,
+    ][..]);
+
+    let mut process = Process::new(&program);
+    assert_eq!(process.get_encoding(), Encoding::Varint);
+    assert_eq!(process.get_program_counter(), 4);
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
+    assert_eq!(process.pop_u64(), Ok(-1000000i64 as u64));
+    assert_eq!(process.pop_u64(), Ok(300));
+    assert_eq!(process.pop_u8(), Ok(-2i8 as u8));
+}
+
+#[test]
+fn test_varint_operands() {
+    let mut builder = ProgramBuilder::with_encoding(Encoding::Varint);
+    builder.push_i16(i16::MIN).push_f32(1.5);
+    builder.with_operand(Instr::push_u8, Instr::type_ptr_off, 300, 2);
+    builder.halt();
+    let program = builder.build().unwrap();
+
+    assert_eq!(&program[4..], &[
+        Instr::push_i16 as u8, Instr::type_int as u8, 0xff, 0xff, 0x03,
+        Instr::push_f32 as u8, Instr::type_int as u8, 0x80, 0x80, 0x80, 0xfe, 0x03,
+        Instr::push_u8 as u8, Instr::type_ptr_off as u8, 0xac, 0x02, 2,
+        Instr::halt as u8,
+    ][..]);
+
+    let mut process = Process::with_memory(&program, 303);
+    process.get_memory_mut()[302] = 7;
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
+    assert_eq!(process.pop_u8(), Ok(7));
+    assert_eq!(process.pop_f32(), Ok(1.5));
+    assert_eq!(process.pop_u16(), Ok(0x8000));
+}
+
+#[test]
+fn test_varint_labels() {
+    // labels past 127 need a second varint byte, the builder and the
+    // assembler lay them out alike
+    let mut builder = ProgramBuilder::with_encoding(Encoding::Varint);
+    let end = builder.label();
+    builder.jmp(end);
+    let mut source = String::from(".encoding varint\njmp end\n");
+    for _ in 0..200 {
+        builder.nop();
+        source.push_str("nop\n");
+    }
+    builder.bind(end).halt();
+    source.push_str("end: halt\n");
+
+    let program = builder.build().unwrap();
+    assert_eq!(&program[4..8], &[Instr::jmp as u8, Instr::type_int as u8, 0xd0, 0x01][..]);
+    assert_eq!(&program[..], &vm::assemble(&source).unwrap()[..]);
+    assert!(vm::verify(&program).is_ok());
+
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process), Ok(Status::Halted(0)));
+}
+
+#[test]
+fn test_varint_tooling() {
+    let program = vm::assemble(SOURCE).unwrap();
+
+    assert_eq!(disassemble(&program), ".encoding varint\n    \
+        push_i8 -2 // 4\n    \
+        push_u64 300 // 7\n    \
+        push_i64 -1000000 // 11\n    \
+        jmp label_22 // 16\n    \
+        push_u8 9 // 19\n\
+        label_22:\n    \
+        halt // 22\n\
+    ");
+    assert_eq!(&vm::assemble(&disassemble(&program)).unwrap()[..], &program[..]);
+
+    assert!(vm::verify(&program).is_ok());
+    let analysis = vm::analyze(&program);
+    assert!(analysis.is_ok());
+    assert_eq!(analysis.get_max_depth(), Some(17));
+
+    // a fixed header only moves the program
+    let fixed = vm::assemble(".encoding fixed\npush_u8 1\n").unwrap();
+    assert_eq!(&fixed[..], &[0xff, b'v', b'm', 0, Instr::push_u8 as u8, Instr::type_int as u8, Instr::size_8 as u8, 1][..]);
+    assert_eq!(disassemble(&fixed), ".encoding fixed\n    push_u8 1 // 4\n");
+}
+
+#[test]
+fn test_varint_invalid() {
+    let mut program = vec![0xff, b'v', b'm', 1, Instr::push_u64 as u8, Instr::type_int as u8];
+    program.extend_from_slice(&[0xff; 10]);
+    program.push(0x01);
+
+    let decoded = vm::decode(&program, 4);
+    assert_eq!(decoded.map_err(|error| error.get_kind()), Err(DecodeErrorKind::InvalidVarint));
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).map_err(|trap| trap.get_kind()), Err(TrapKind::InvalidVarint));
+
+    // ends inside the varint
+    let program = [0xff, b'v', b'm', 1, Instr::push_u16 as u8, Instr::type_int as u8, 0x80];
+    let mut process = Process::new(&program);
+    assert_eq!(vm::run(&mut process).map_err(|trap| trap.get_kind()), Err(TrapKind::TruncatedProgram));
+
+    let error = vm::assemble("push_u8 1\n.encoding varint\n").err().unwrap();
+    assert_eq!(error.get_kind(), vm::AssembleErrorKind::InvalidDirective);
+}
@@ -0,0 +1,110 @@
+extern crate vm;
+
+
+use vm::{Instr, VerifyError, VerifyErrorKind};
+
+
+fn errors(program: &[u8]) -> Vec<(VerifyErrorKind, usize)> {
+    vm::verify(program).unwrap_err().iter()
+        .map(|error: &VerifyError| (error.get_kind(), error.get_offset()))
+        .collect()
+}
+
+
+#[test]
+fn test_verify_valid() {
+    let program = vm::assemble("
+        call function
+        jmp end
+        function:
+            push_u8 ptr_off 1, 2
+            checked add_u8
+            sat neg_i64
+            if_jmp function
+            ret
+        end:
+            halt
+    ").unwrap();
+
+    assert!(vm::verify(&program).is_ok());
+    assert!(vm::verify(&[]).is_ok());
+}
+
+#[test]
+fn test_verify_reports_all() {
+    let program = [
+        // targets the add_f32 prefixed by sat
+        Instr::jmp as u8, Instr::type_int as u8, Instr::size_8 as u8, 5,
+        Instr::sat as u8, Instr::add_f32 as u8,
+        Instr::call as u8, Instr::type_int as u8, Instr::size_8 as u8, 200,
+        Instr::checked as u8,
+    ];
+
+    assert_eq!(errors(&program), vec![
+        (VerifyErrorKind::InvalidTarget(5), 0),
+        (VerifyErrorKind::InvalidInstr(Instr::add_f32 as u8), 5),
+        (VerifyErrorKind::InvalidTarget(200), 6),
+        (VerifyErrorKind::TruncatedProgram, 11),
+    ]);
+}
+
+#[test]
+fn test_verify_decode_errors() {
+    assert_eq!(errors(&[Instr::type_int as u8]), vec![(VerifyErrorKind::InvalidInstr(Instr::type_int as u8), 0)]);
+    assert_eq!(
+        errors(&[Instr::push_u8 as u8, Instr::size_8 as u8, 0]),
+        vec![(VerifyErrorKind::InvalidType(Instr::size_8 as u8), 1)]
+    );
+
+    // a bad size byte is reported once, the bytes after it are not decoded
+    // as instructions
+    let program = [
+        Instr::push_u32 as u8, Instr::type_int as u8, Instr::push_u8 as u8,
+        Instr::type_int as u8, Instr::size_8 as u8, 0xff, Instr::ext as u8, 0xff,
+    ];
+    assert_eq!(errors(&program), vec![(VerifyErrorKind::InvalidSize(Instr::push_u8 as u8), 2)]);
+
+    // targets past the bad instruction cannot be checked, ones before it are
+    let program = [
+        Instr::jmp as u8, Instr::type_int as u8, Instr::size_8 as u8, 12,
+        Instr::jmp as u8, Instr::type_int as u8, Instr::size_8 as u8, 2,
+        Instr::push_u8 as u8, Instr::type_int as u8, 0, 1,
+        Instr::halt as u8,
+    ];
+    assert_eq!(errors(&program), vec![
+        (VerifyErrorKind::InvalidTarget(2), 4),
+        (VerifyErrorKind::InvalidSize(0), 10),
+    ]);
+}
+
+#[test]
+fn test_verify_prefixed_target() {
+    let program = vm::assemble("
+        jmp add
+        checked
+    add:
+        add_u8
+    ").unwrap();
+    assert_eq!(errors(&program), vec![(VerifyErrorKind::InvalidTarget(5), 0)]);
+
+    let program = vm::assemble("
+        jmp add
+        checked add_u8
+    add:
+        nop
+    ").unwrap();
+    assert!(vm::verify(&program).is_ok());
+}
+
+#[test]
+fn test_verify_truncated() {
+    let program = [Instr::push_u32 as u8, Instr::type_int as u8, Instr::size_32 as u8, 0, 0];
+    assert_eq!(errors(&program), vec![(VerifyErrorKind::TruncatedProgram, 5)]);
+
+    // jumping to the end of the program is outside of it
+    let program = [Instr::jmp as u8, Instr::type_int as u8, Instr::size_8 as u8, 4];
+    assert_eq!(errors(&program), vec![(VerifyErrorKind::InvalidTarget(4), 0)]);
+
+    let error = vm::verify(&program).unwrap_err()[0];
+    assert_eq!(error.to_string(), "invalid jump target 4 at 0");
+}